@@ -0,0 +1,26 @@
+use anyhow::bail;
+
+use crate::error::ParselyResult;
+
+// Helpers for types which hold an unsigned integer whose width (up to 64 bits) is given by const
+// generics, such as `Reserved<N>` and `UFixed<INT, FRAC>`.
+
+/// Check that `bits` is a supported width, i.e. 1-64 bits.  This is meant to be evaluated in a
+/// `BITS` constant, so that an unsupported width fails at compile time.
+pub(crate) const fn checked_width(bits: u32) -> u32 {
+    assert!(bits > 0 && bits <= u64::BITS, "Width must be 1-64 bits");
+    bits
+}
+
+/// The largest value that fits in `bits` bits
+pub(crate) const fn max_uint(bits: u32) -> u64 {
+    u64::MAX >> (u64::BITS - bits)
+}
+
+/// Check that `value` fits in `bits` bits
+pub(crate) fn check_uint(value: u64, bits: u32) -> ParselyResult<u64> {
+    if value > max_uint(bits) {
+        bail!("Value {value} doesn't fit in {bits} bits");
+    }
+    Ok(value)
+}
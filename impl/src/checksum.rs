@@ -0,0 +1,318 @@
+use std::ops::Range;
+
+use anyhow::bail;
+use bits_io::prelude::*;
+
+use crate::{
+    error::ParselyResult,
+    parsely_write::{backpatch, ParselyWrite},
+};
+
+/// A checksum algorithm which can be used for a `checksum` field.  Implement this to use a custom
+/// algorithm, and give its path as the field's `checksum` attribute.
+pub trait Checksum {
+    /// Compute the checksum of `data`
+    fn checksum(data: &[u8]) -> u64;
+}
+
+/// The Internet checksum (RFC 1071): the ones' complement of the ones' complement sum of the data
+/// as big-endian 16 bit words, as used by IPv4, UDP and TCP.
+#[derive(Debug)]
+pub struct InternetChecksum;
+
+impl Checksum for InternetChecksum {
+    fn checksum(data: &[u8]) -> u64 {
+        let mut sum = data
+            .chunks(2)
+            .map(|word| u64::from(u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)])))
+            .sum::<u64>();
+        while sum > 0xffff {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+        !sum & 0xffff
+    }
+}
+
+/// The parameters of a CRC algorithm, as given in the "Catalogue of parametrised CRC algorithms"
+struct CrcParams {
+    width: u32,
+    poly: u64,
+    init: u64,
+    reflect: bool,
+    xor_out: u64,
+}
+
+impl CrcParams {
+    fn compute(&self, data: &[u8]) -> u64 {
+        let mask = u64::MAX >> (64 - self.width);
+        let crc = if self.reflect {
+            let poly = self.poly.reverse_bits() >> (64 - self.width);
+            data.iter().fold(self.init, |crc, byte| {
+                (0..8).fold(crc ^ u64::from(*byte), |crc, _| {
+                    if crc & 1 != 0 {
+                        (crc >> 1) ^ poly
+                    } else {
+                        crc >> 1
+                    }
+                })
+            })
+        } else {
+            let top_bit = 1 << (self.width - 1);
+            data.iter().fold(self.init, |crc, byte| {
+                (0..8).fold(crc ^ (u64::from(*byte) << (self.width - 8)), |crc, _| {
+                    if crc & top_bit != 0 {
+                        ((crc << 1) ^ self.poly) & mask
+                    } else {
+                        (crc << 1) & mask
+                    }
+                })
+            })
+        };
+        (crc ^ self.xor_out) & mask
+    }
+}
+
+macro_rules! crc_algorithm {
+    ($(#[$doc:meta])* $name:ident, $width:expr, $poly:expr, $init:expr, $reflect:expr, $xor_out:expr) => {
+        $(#[$doc])*
+        #[derive(Debug)]
+        pub struct $name;
+
+        impl Checksum for $name {
+            fn checksum(data: &[u8]) -> u64 {
+                CrcParams {
+                    width: $width,
+                    poly: $poly,
+                    init: $init,
+                    reflect: $reflect,
+                    xor_out: $xor_out,
+                }
+                .compute(data)
+            }
+        }
+    };
+}
+
+crc_algorithm!(
+    /// CRC-8/SMBUS
+    Crc8, 8, 0x07, 0, false, 0
+);
+crc_algorithm!(
+    /// CRC-16/ARC (also known as CRC-16/IBM)
+    Crc16, 16, 0x8005, 0, true, 0
+);
+crc_algorithm!(
+    /// CRC-16/IBM-3740 (also known as CRC-16/CCITT-FALSE)
+    Crc16Ccitt, 16, 0x1021, 0xffff, false, 0
+);
+crc_algorithm!(
+    /// CRC-16/KERMIT (the 'true' CCITT CRC)
+    Crc16Kermit, 16, 0x1021, 0, true, 0
+);
+crc_algorithm!(
+    /// CRC-32/ISO-HDLC, as used by PNG, Ethernet and zip
+    Crc32, 32, 0x04c11db7, 0xffffffff, true, 0xffffffff
+);
+crc_algorithm!(
+    /// CRC-32/ISCSI (also known as CRC-32C or Castagnoli), as used by SCTP
+    Crc32c, 32, 0x1edc6f41, 0xffffffff, true, 0xffffffff
+);
+
+/// Adler-32 (RFC 1950), as used by zlib
+#[derive(Debug)]
+pub struct Adler32;
+
+impl Checksum for Adler32 {
+    fn checksum(data: &[u8]) -> u64 {
+        let (a, b) = data.iter().fold((1u64, 0u64), |(a, b), byte| {
+            let a = (a + u64::from(*byte)) % 65521;
+            (a, (b + a) % 65521)
+        });
+        (b << 16) | a
+    }
+}
+
+/// Fletcher-16, over bytes
+#[derive(Debug)]
+pub struct Fletcher16;
+
+impl Checksum for Fletcher16 {
+    fn checksum(data: &[u8]) -> u64 {
+        let (a, b) = data.iter().fold((0u64, 0u64), |(a, b), byte| {
+            let a = (a + u64::from(*byte)) % 255;
+            (a, (b + a) % 255)
+        });
+        (b << 8) | a
+    }
+}
+
+/// Fletcher-32, over little-endian 16 bit words (an odd trailing byte is padded with zero)
+#[derive(Debug)]
+pub struct Fletcher32;
+
+impl Checksum for Fletcher32 {
+    fn checksum(data: &[u8]) -> u64 {
+        let (a, b) = data.chunks(2).fold((0u64, 0u64), |(a, b), word| {
+            let word = u16::from_le_bytes([word[0], *word.get(1).unwrap_or(&0)]);
+            let a = (a + u64::from(word)) % 65535;
+            (a, (b + a) % 65535)
+        });
+        (b << 16) | a
+    }
+}
+
+/// The error returned when a checksum field doesn't match the checksum of the data it covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumError {
+    pub field: String,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl std::fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Checksum field '{}' has value {:#x}, but the data's checksum is {:#x}",
+            self.field, self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ChecksumError {}
+
+/// A [`BitBuf`] which records the bits that are read from the buffer it wraps, so that a checksum
+/// can be computed over them after they've been read.
+pub struct RecordingBuf<B> {
+    inner: B,
+    recorded: BitVec,
+}
+
+impl<B: BitBuf> RecordingBuf<B> {
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            recorded: BitVec::new(),
+        }
+    }
+
+    /// The number of bits that have been read so far
+    pub fn len_bits(&self) -> usize {
+        self.recorded.len()
+    }
+
+    /// The bits that have been read so far
+    pub fn recorded(&self) -> &BitSlice {
+        self.recorded.as_bitslice()
+    }
+
+    /// Take the bits that have been read
+    pub fn into_recorded(self) -> BitVec {
+        self.recorded
+    }
+}
+
+impl<B: BitBuf> BitBuf for RecordingBuf<B> {
+    fn advance_bits(&mut self, mut count: usize) {
+        while count > 0 {
+            let chunk = self.inner.chunk_bits();
+            let len = count.min(chunk.len());
+            if len == 0 {
+                // Let the inner buffer deal with advancing past its end
+                self.inner.advance_bits(count);
+                return;
+            }
+            self.recorded.extend_from_bitslice(&chunk[..len]);
+            self.inner.advance_bits(len);
+            count -= len;
+        }
+    }
+
+    fn remaining_bits(&self) -> usize {
+        self.inner.remaining_bits()
+    }
+
+    fn chunk_bits(&self) -> &BitSlice {
+        self.inner.chunk_bits()
+    }
+
+    fn chunk_bytes(&self) -> &[u8] {
+        self.inner.chunk_bytes()
+    }
+
+    fn byte_aligned(&self) -> bool {
+        self.inner.byte_aligned()
+    }
+}
+
+/// Compute the checksum `C` over the bits of `data` in `range`, treating the bits in `field` (which
+/// hold the checksum itself) as zeros.
+pub fn compute_checksum<C: Checksum>(
+    data: &BitSlice,
+    range: Range<usize>,
+    field: Range<usize>,
+) -> ParselyResult<u64> {
+    if range.start > range.end || range.end > data.len() {
+        bail!(
+            "Checksum range {range:?} is invalid for {} bits",
+            data.len()
+        );
+    }
+    if !range.len().is_multiple_of(8) {
+        bail!(
+            "Checksum range must cover a whole number of bytes, but covers {} bits",
+            range.len()
+        );
+    }
+    let mut bits = data[range.clone()].to_bitvec();
+    bits.force_align();
+    let (zeroed_start, zeroed_end) = (field.start.max(range.start), field.end.min(range.end));
+    if zeroed_start < zeroed_end {
+        bits[zeroed_start - range.start..zeroed_end - range.start].fill(false);
+    }
+    Ok(C::checksum(bits.as_raw_slice()))
+}
+
+/// Compute the checksum `C` over the bits of `buf` in `range` and write it (as a `V`) over the bits
+/// in `field`.
+pub fn write_checksum<C, V, T>(
+    buf: &mut BitsMut,
+    range: Range<usize>,
+    field: Range<usize>,
+) -> ParselyResult<()>
+where
+    C: Checksum,
+    V: TryFrom<u64> + ParselyWrite<BitsMut, Ctx = ()>,
+    <V as TryFrom<u64>>::Error: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    T: ByteOrder,
+{
+    let checksum = compute_checksum::<C>(buf, range, field.clone())?;
+    backpatch::<V, T>(buf, field, checksum)
+}
+
+/// Compute the checksum `C` over the bits of `data` in `range` and check that it matches `value`,
+/// the value read from the field `field_name` (whose bits are `field`).  Fails with a
+/// [`ChecksumError`] if it doesn't.
+pub fn verify_checksum<C, V>(
+    data: &BitSlice,
+    range: Range<usize>,
+    field: Range<usize>,
+    field_name: &str,
+    value: &V,
+) -> ParselyResult<()>
+where
+    C: Checksum,
+    V: Copy + Into<u64>,
+{
+    let expected = compute_checksum::<C>(data, range, field)?;
+    let found = (*value).into();
+    if found != expected {
+        return Err(ChecksumError {
+            field: field_name.to_owned(),
+            expected,
+            found,
+        }
+        .into());
+    }
+    Ok(())
+}
@@ -1,5 +1,11 @@
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote, ToTokens};
+
+use crate::{
+    code_gen::parsely_common_field_data::ParselyCommonFieldData,
+    model_types::{Alignment, ChecksumBound, ChecksumRange},
+    syn_helpers::MemberExts,
+};
 
 pub(crate) fn wrap_in_optional(condition: &syn::Expr, inner: TokenStream) -> TokenStream {
     quote! {
@@ -10,3 +16,146 @@ pub(crate) fn wrap_in_optional(condition: &syn::Expr, inner: TokenStream) -> Tok
         }
     }
 }
+
+/// The variable which holds the buffer's remaining bits at the start of a struct or enum's read or
+/// write, for alignments which are relative to the start of the message.
+pub(crate) fn message_start_ident() -> syn::Ident {
+    format_ident!("__message_start_remaining_bits")
+}
+
+/// Generate a statement which records the buffer's remaining bits at the start of a struct or
+/// enum's read or write, if any of the given alignments are relative to the start of the message.
+/// `remaining_bits` is the expression which gets the buffer's remaining bits.
+pub(crate) fn generate_message_start<'a>(
+    mut alignments: impl Iterator<Item = &'a Alignment>,
+    remaining_bits: TokenStream,
+) -> Option<TokenStream> {
+    let message_start = message_start_ident();
+    alignments
+        .any(|alignment| alignment.relative_to_message)
+        .then(|| quote! { let #message_start = #remaining_bits; })
+}
+
+/// Check that the alignment of a struct or enum doesn't try to be relative to the message, since
+/// it is the message.
+pub(crate) fn check_message_alignment(
+    name: &syn::Ident,
+    alignment: Option<&Alignment>,
+) -> anyhow::Result<()> {
+    if alignment.is_some_and(|alignment| alignment.relative_to_message) {
+        anyhow::bail!("'{name}': 'relative_to_message' can only be used on fields and variants");
+    }
+    Ok(())
+}
+
+/// The variables which hold the positions of the start and end of a field in the buffer it's read
+/// from or written to, for the checksums and lengths which refer to them.
+pub(crate) fn field_position_idents(field: &syn::Member) -> (syn::Ident, syn::Ident) {
+    let name = field.as_variable_name();
+    (
+        format_ident!("__{name}_start_position"),
+        format_ident!("__{name}_end_position"),
+    )
+}
+
+/// The variable which holds the position of the end of a struct's fields, for the checksums which
+/// refer to it.
+pub(crate) fn struct_end_position_ident() -> syn::Ident {
+    format_ident!("__struct_end_position")
+}
+
+/// Generate the statements which record the start and end positions of a field, if they're needed
+pub(crate) fn generate_position_markers(
+    field: &ParselyCommonFieldData,
+) -> (Option<TokenStream>, Option<TokenStream>) {
+    if !field.position_markers {
+        return (None, None);
+    }
+    let (start, end) = field_position_idents(&field.ident);
+    (
+        Some(quote! {
+            #[allow(unused_variables)]
+            let #start = buf.len_bits();
+        }),
+        Some(quote! {
+            #[allow(unused_variables)]
+            let #end = buf.len_bits();
+        }),
+    )
+}
+
+/// Generate the range expression for the bits covered by the checksum in the field `this`
+pub(crate) fn checksum_range_tokens(range: &ChecksumRange, this: &syn::Member) -> TokenStream {
+    let position = |bound: &ChecksumBound, field_end: bool| match bound {
+        ChecksumBound::Start => quote! { 0 },
+        ChecksumBound::End => struct_end_position_ident().into_token_stream(),
+        ChecksumBound::This | ChecksumBound::Field(_) => {
+            let field = match bound {
+                ChecksumBound::Field(field) => field,
+                _ => this,
+            };
+            let (start, end) = field_position_idents(field);
+            if field_end {
+                end.into_token_stream()
+            } else {
+                start.into_token_stream()
+            }
+        }
+    };
+    let start = position(&range.start, false);
+    let end = position(&range.end, range.inclusive);
+    quote! { #start..#end }
+}
+
+/// Get each checksum field of a struct along with the fields its range refers to
+pub(crate) fn checksum_references<'a>(
+    fields: impl Iterator<Item = &'a ParselyCommonFieldData>,
+) -> Vec<(syn::Member, Vec<syn::Member>)> {
+    fields
+        .filter_map(|field| {
+            let checksum = field.checksum.as_ref()?;
+            Some((
+                field.ident.clone(),
+                checksum.range.fields().cloned().collect(),
+            ))
+        })
+        .collect()
+}
+
+/// Mark the fields of the struct `name` whose positions need to be recorded because they're
+/// backpatched once the struct has been written (checksums and lengths) or are referred to by a
+/// backpatched field.  `references` holds each backpatched field along with the fields it refers
+/// to.  Returns whether the struct has any backpatched fields.
+pub(crate) fn mark_field_positions<'a>(
+    name: &syn::Ident,
+    fields: impl Iterator<Item = &'a mut ParselyCommonFieldData>,
+    references: &[(syn::Member, Vec<syn::Member>)],
+) -> anyhow::Result<bool> {
+    if references.is_empty() {
+        return Ok(false);
+    }
+    let mut fields = fields.collect::<Vec<_>>();
+    if fields.iter().any(|f| f.trailing_padding.is_some()) {
+        anyhow::bail!(
+            "'{name}': 'checksum' and 'length_of' fields can't be combined with 'trailing_padding'"
+        );
+    }
+    let mut marked = Vec::new();
+    for (field, referenced_fields) in references {
+        marked.push(field);
+        for referenced in referenced_fields {
+            if !fields.iter().any(|f| &f.ident == referenced) {
+                anyhow::bail!(
+                    "'{name}': field '{}' refers to unknown field '{}'",
+                    field.as_friendly_string(),
+                    referenced.as_friendly_string()
+                );
+            }
+            marked.push(referenced);
+        }
+    }
+    for field in fields.iter_mut() {
+        field.position_markers = marked.contains(&&field.ident);
+    }
+    Ok(true)
+}
@@ -1,5 +1,6 @@
 // pub(crate) mod gen_write;
 pub(crate) mod helpers;
 pub(crate) mod parsely_common_field_data;
+pub(crate) mod parsely_common_variant_data;
 pub(crate) mod read;
 pub(crate) mod write;
@@ -1,6 +1,11 @@
+use darling::util::Override;
+
 use crate::{
+    model_types::{
+        Alignment, ConstFields, FieldChecksum, FieldSteps, Hooks, SkipBits, TrailingPadding,
+    },
     syn_helpers::{MemberExts, TypeExts},
-    Assertion, Context, MapExpr,
+    Context, ParselyCommonFieldReceiver,
 };
 
 /// Items that are needed for both reading and writing a field to/from a buffer.
@@ -10,18 +15,134 @@ pub(crate) struct ParselyCommonFieldData {
     /// The field's type
     pub(crate) ty: syn::Type,
 
-    pub(crate) assertion: Option<Assertion>,
     /// Values that need to be passed as context to this fields read or write method
     pub(crate) context: Option<Context>,
+    /// Values that need to be passed as context to the read or write method of each element of a
+    /// collection field.
+    pub(crate) elem_context: Option<Context>,
 
-    /// An optional mapping that will be applied to the read value
-    pub(crate) map: Option<MapExpr>,
     /// An optional indicator that this field is or needs to be aligned to the given byte alignment
     /// via padding.
-    pub(crate) alignment: Option<usize>,
+    pub(crate) alignment: Option<Alignment>,
+    /// An optional total size in bytes that this field is padded to
+    pub(crate) pad_to_bytes: Option<syn::Expr>,
+    /// An optional type that the value will be converted to and from when writing it to and
+    /// reading it from the buffer.  For wrapper types this applies to the inner type.
+    pub(crate) as_type: Option<syn::Type>,
+    /// Constant values that come before this field in the buffer
+    pub(crate) const_fields: ConstFields,
+    /// Set if this field is the flag for trailing padding at the end of its struct
+    pub(crate) trailing_padding: Option<TrailingPadding>,
+    /// A number of reserved bits that come before this field (and its constants) in the buffer
+    pub(crate) skip_bits: Option<SkipBits>,
+    /// Expressions which are evaluated before and after this field is read or written
+    pub(crate) hooks: Hooks,
+    /// Set if this field isn't in the buffer, holding the expression which gives its value when
+    /// reading
+    pub(crate) skip: Option<syn::Expr>,
+    /// Set if this field is a checksum over part of its struct
+    pub(crate) checksum: Option<FieldChecksum>,
+    /// Set if this field's start and end positions need to be recorded, because it's backpatched
+    /// when writing or a backpatched field refers to it
+    pub(crate) position_markers: bool,
+    /// Assertions and maps to apply to the value, in the order they were declared
+    pub(crate) steps: FieldSteps,
+    /// Assertions and maps to apply to each element of a collection field, in the order they were
+    /// declared
+    pub(crate) elem_steps: FieldSteps,
 }
 
 impl ParselyCommonFieldData {
+    pub(crate) fn from_receiver(
+        ident: syn::Member,
+        ty: syn::Type,
+        receiver: ParselyCommonFieldReceiver,
+    ) -> Self {
+        let field_name = ident.as_friendly_string();
+        let has_elem_attributes =
+            receiver.elem_context.is_some() || !receiver.steps.elem.is_empty();
+        if has_elem_attributes && !ty.is_collection() {
+            panic!(
+                "Field '{field_name}': 'elem_' attributes can only be used on collection fields"
+            );
+        }
+        if receiver.context.is_some() && receiver.elem_context.is_some() {
+            panic!("Field '{field_name}': 'context' can't be combined with 'elem_context'");
+        }
+        if receiver.as_type.is_some()
+            && (receiver.steps.field.has_map() || receiver.steps.elem.has_map())
+        {
+            panic!("Field '{field_name}': 'as' attribute can't be combined with 'map'");
+        }
+        if receiver.default.is_some() && !receiver.skip {
+            panic!("Field '{field_name}': 'default' can only be used with 'skip'");
+        }
+        let hooks = Hooks {
+            pre_read: receiver.pre_read,
+            post_read: receiver.post_read,
+            pre_write: receiver.pre_write,
+            post_write: receiver.post_write,
+        };
+        if receiver.skip
+            && (receiver.context.is_some()
+                || receiver.elem_context.is_some()
+                || receiver.alignment.is_some()
+                || receiver.pad_to_bytes.is_some()
+                || receiver.as_type.is_some()
+                || !receiver.const_field.is_empty()
+                || receiver.trailing_padding.is_some()
+                || receiver.skip_bits.is_some()
+                || receiver.checksum.is_some()
+                || !receiver.steps.field.is_empty()
+                || !receiver.steps.elem.is_empty()
+                || !hooks.is_empty())
+        {
+            panic!(
+                "Field '{field_name}': 'skip' can't be combined with attributes for reading or writing the field"
+            );
+        }
+        let skip = receiver.skip.then(|| match receiver.default {
+            Some(Override::Explicit(expr)) => expr,
+            _ => syn::parse_quote! { ::core::default::Default::default() },
+        });
+        if receiver.over.is_some() && receiver.checksum.is_none() {
+            panic!("Field '{field_name}': 'over' can only be used with 'checksum'");
+        }
+        if receiver.checksum.is_some()
+            && (ty.is_wrapped()
+                || receiver.as_type.is_some()
+                || receiver.steps.field.has_map()
+                || receiver.alignment.is_some()
+                || receiver.pad_to_bytes.is_some())
+        {
+            panic!(
+                "Field '{field_name}': a checksum field can't be an Option or collection or have 'as', 'map', 'alignment' or 'pad_to_bytes'"
+            );
+        }
+        let checksum = receiver.checksum.map(|algorithm| FieldChecksum {
+            algorithm,
+            range: receiver.over.unwrap_or_default(),
+        });
+        Self {
+            ident,
+            ty,
+            context: receiver.context,
+            elem_context: receiver.elem_context,
+            alignment: receiver.alignment,
+            pad_to_bytes: receiver.pad_to_bytes,
+            as_type: receiver.as_type,
+            const_fields: receiver.const_field,
+            skip_bits: receiver.skip_bits,
+            trailing_padding: receiver.trailing_padding,
+            hooks,
+            skip,
+            checksum,
+            position_markers: false,
+            steps: receiver.steps.field,
+            elem_steps: receiver.steps.elem,
+        }
+    }
+
     /// Get the 'buffer type' of this field (the type that will be used when reading from or
     /// writing to the buffer): for wrapper types (like [`Option`] or [`Vec`]), this will be the
     /// inner type.
@@ -42,8 +163,20 @@ impl ParselyCommonFieldData {
                 "Read context for field '{}'",
                 self.ident.as_friendly_string(),
             ))
+        } else if let Some(ref elem_context) = self.elem_context {
+            elem_context.expressions(&format!(
+                "Element context for field '{}'",
+                self.ident.as_friendly_string(),
+            ))
         } else {
             vec![]
         }
     }
+
+    /// Whether any attributes which apply to the individual elements of a collection field are
+    /// present.  When they are, the index of the element (`idx`) and the elements before it
+    /// (`items`) are made available to them.
+    pub(crate) fn has_elem_attributes(&self) -> bool {
+        self.elem_context.is_some() || !self.elem_steps.is_empty()
+    }
 }
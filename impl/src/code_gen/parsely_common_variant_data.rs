@@ -0,0 +1,103 @@
+use anyhow::bail;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    model_types::{Alignment, Assertion, TypedFnArgList},
+    Context, ParselyCommonVariantReceiver,
+};
+
+/// Items that are needed for both reading and writing an enum variant to/from a buffer.
+#[derive(Debug)]
+pub(crate) struct ParselyCommonVariantData {
+    pub(crate) enum_name: syn::Ident,
+    pub(crate) ident: syn::Ident,
+    /// Whether this variant is used for keys that don't match any other variant
+    pub(crate) unknown: bool,
+    /// An optional indicator that this variant's fields need to be padded to the given byte
+    /// alignment.
+    pub(crate) alignment: Option<Alignment>,
+    /// An assertion that is checked against the variant's value after reading it or before
+    /// writing it
+    pub(crate) assertion: Option<Assertion>,
+    /// Values that this variant's fields need, and the expressions they're assigned from
+    pub(crate) required_context: TypedFnArgList,
+    pub(crate) context: Option<Context>,
+    /// The length, in bytes, of this variant's fields
+    pub(crate) length_bytes: Option<syn::Expr>,
+}
+
+impl ParselyCommonVariantData {
+    pub(crate) fn from_receiver(
+        enum_name: syn::Ident,
+        ident: syn::Ident,
+        receiver: ParselyCommonVariantReceiver,
+    ) -> anyhow::Result<Self> {
+        let context_len = receiver.context.as_ref().map(|c| c.len()).unwrap_or(0);
+        if context_len != receiver.required_context.0.len() {
+            bail!(
+                "Variant '{enum_name}::{ident}': 'context' needs a value for each item in \
+                'required_context'"
+            );
+        }
+        if receiver.unknown
+            && (receiver.alignment.is_some()
+                || receiver.assertion.is_some()
+                || receiver.context.is_some()
+                || receiver.length_bytes.is_some())
+        {
+            bail!("Unknown variant '{enum_name}::{ident}' can't have any other attributes");
+        }
+        Ok(Self {
+            enum_name,
+            ident,
+            unknown: receiver.unknown,
+            alignment: receiver.alignment,
+            assertion: receiver.assertion,
+            required_context: receiver.required_context,
+            context: receiver.context,
+            length_bytes: receiver.length_bytes,
+        })
+    }
+
+    /// The name of this variant, as used in error messages
+    pub(crate) fn friendly_name(&self) -> String {
+        format!("{}::{}", self.enum_name, self.ident)
+    }
+
+    /// Generate statements which assign this variant's required context from its context
+    /// expressions.  If given, `key` is an expression which refers to the enum's key and is made
+    /// available to those expressions as `key`.
+    pub(crate) fn context_bindings(&self, key: Option<TokenStream>) -> TokenStream {
+        let Some(ref context) = self.context else {
+            return quote! {};
+        };
+        let (names, types) = (self.required_context.names(), self.required_context.types());
+        let values =
+            context.expressions(&format!("Context for variant '{}'", self.friendly_name()));
+        let key_binding = key.map(|key| {
+            quote! {
+                #[allow(unused_variables)]
+                let key = #key;
+            }
+        });
+        // The values might only be needed when reading (or writing)
+        quote! {
+            #key_binding
+            #[allow(unused_variables)]
+            let (#(#names,)*): (#(#types,)*) = (#(#values,)*);
+        }
+    }
+
+    /// Generate statements which check this variant's assertion (if there is one) against `value`
+    /// (a reference expression)
+    pub(crate) fn assertion_tokens(&self, value: TokenStream) -> TokenStream {
+        match self.assertion {
+            Some(ref assertion) => assertion.to_check_tokens(
+                &format!("value of variant '{}'", self.friendly_name()),
+                &value,
+            ),
+            None => quote! {},
+        }
+    }
+}
@@ -1,28 +1,68 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use crate::{model_types::CollectionLimit, syn_helpers::MemberExts};
+use crate::{
+    code_gen::{
+        helpers::{
+            checksum_range_tokens, field_position_idents, message_start_ident,
+            struct_end_position_ident,
+        },
+        read::parsely_read_field_data::ParselyReadFieldData,
+    },
+    get_crate_name,
+    model_types::{Alignment, CollectionLimit, SkipBits},
+    syn_helpers::MemberExts,
+};
 
-pub(crate) fn generate_plain_read(ty: &syn::Type, context_values: &[syn::Expr]) -> TokenStream {
+/// Generate a read of a value of type `ty`.  If `as_type` is given, a value of that type is read
+/// instead and converted to `ty` via `TryFrom`.
+pub(crate) fn generate_plain_read(
+    ty: &syn::Type,
+    as_type: Option<&syn::Type>,
+    context_values: &[syn::Expr],
+) -> TokenStream {
+    if let Some(as_type) = as_type {
+        let as_type_string = quote! { #as_type }.to_string();
+        quote! {
+            <#as_type>::read::<T>(buf, (#(#context_values,)*)).and_then(|v| {
+                <#ty as TryFrom<#as_type>>::try_from(v)
+                    .map_err(|e| anyhow!(e))
+                    .with_context(|| format!("Converting from {}", #as_type_string))
+            })
+        }
+    } else {
+        quote! {
+            <#ty>::read::<T>(buf, (#(#context_values,)*))
+        }
+    }
+}
+
+/// Generate a read of a value via the custom reader function `reader`
+pub(crate) fn generate_reader_read(
+    reader: &syn::Path,
+    context_values: &[syn::Expr],
+) -> TokenStream {
     quote! {
-        #ty::read::<T>(buf, (#(#context_values,)*))
+        #reader::<_, T>(buf, (#(#context_values,)*))
     }
 }
 
+/// Generate a read of a collection of `ty` values, where `element_read` is an expression that
+/// reads a single element.  `element_read` (and the collection's limit) can refer to the index of
+/// the element being read (`idx`) and the elements read so far (`items`).
 pub(crate) fn generate_collection_read(
     limit: &CollectionLimit,
     ty: &syn::Type,
-    context_values: &[syn::Expr],
+    element_read: TokenStream,
 ) -> TokenStream {
-    let plain_read = generate_plain_read(ty, context_values);
     match limit {
         CollectionLimit::Count(count) => {
             quote! {
                 (|| {
                     let item_count = #count;
                     let mut items: Vec<#ty> = Vec::with_capacity(item_count as usize);
-                    for idx in 0..item_count {
-                        let item = #plain_read.with_context(|| format!("Index {idx}"))?;
+                    for idx in 0..item_count as usize {
+                        let item = #element_read.with_context(|| format!("Index {idx}"))?;
                         items.push(item);
                     }
                     ParselyResult::Ok(items)
@@ -34,13 +74,14 @@ pub(crate) fn generate_collection_read(
             // Since this is multiple statements we wrap it in a closure
             quote! {
                 (|| {
-                    let mut values: Vec<ParselyResult<#ty>> = Vec::new();
-                    let mut idx = 0;
+                    let mut items: Vec<#ty> = Vec::new();
+                    let mut idx: usize = 0;
                     while (#pred) {
-                        values.push(#plain_read.with_context( || format!("Read {idx}")));
+                        let item = #element_read.with_context(|| format!("Read {idx}"))?;
+                        items.push(item);
                         idx += 1
                     }
-                    values.into_iter().collect::<ParselyResult<Vec<#ty>>>()
+                    ParselyResult::Ok(items)
                 })()
             }
         }
@@ -49,20 +90,207 @@ pub(crate) fn generate_collection_read(
 
 pub(crate) fn wrap_read_with_padding_handling(
     element_ident: &syn::Member,
-    alignment: usize,
+    alignment: &Alignment,
     inner: TokenStream,
 ) -> TokenStream {
-    let bytes_read_before_ident = format_ident!(
-        "__bytes_read_before_{}_read",
+    let bits_remaining_before_ident = format_ident!(
+        "__bits_remaining_before_{}_read",
         element_ident.as_friendly_string()
     );
+    let origin_offset = alignment
+        .origin_offset_tokens(&bits_remaining_before_ident, &message_start_ident())
+        .map(|origin_offset| quote! { + #origin_offset });
+    let alignment_bits = alignment.bits;
+    quote! {
+        let #bits_remaining_before_ident = buf.remaining_bits();
+
+        #inner
+
+        {
+            let offset = #bits_remaining_before_ident - buf.remaining_bits() #origin_offset;
+            let padding_bits = (#alignment_bits - offset % #alignment_bits) % #alignment_bits;
+            for _ in 0..padding_bits {
+                buf.get_u1().context("consuming padding")?;
+            }
+        }
+    }
+}
+
+/// Wrap `inner` so that a total of `size_bytes` bytes are read for `element_ident`: whatever
+/// `inner` didn't read is skipped, and reading fails if it read more than that.
+pub(crate) fn wrap_read_with_size_padding(
+    element_ident: &syn::Member,
+    size_bytes: &syn::Expr,
+    inner: TokenStream,
+) -> TokenStream {
+    let element_name = element_ident.as_friendly_string();
+    let bits_remaining_before_ident =
+        format_ident!("__bits_remaining_before_{}_sized_read", element_name);
     quote! {
-        let #bytes_read_before_ident = buf.remaining_bytes();
+        let #bits_remaining_before_ident = buf.remaining_bits();
 
         #inner
 
-        while (#bytes_read_before_ident - buf.remaining_bytes()) % #alignment != 0 {
-            buf.get_u8().context("consuming padding")?;
+        {
+            let size_bytes: usize = (#size_bytes).into_parsely_result().with_context(|| format!("Size of '{}'", #element_name))?;
+            let bits_read = #bits_remaining_before_ident - buf.remaining_bits();
+            if bits_read > size_bytes * 8 {
+                bail!("'{}' read {} bits, more than its size of {} bytes", #element_name, bits_read, size_bytes);
+            }
+            let padding_bits = size_bytes * 8 - bits_read;
+            if buf.remaining_bits() < padding_bits {
+                bail!("'{}' needs {} bits of padding but only {} remain", #element_name, padding_bits, buf.remaining_bits());
+            }
+            buf.advance_bits(padding_bits);
+        }
+    }
+}
+
+/// The variable which holds the buffer's remaining bits at the start of a struct with trailing
+/// padding, when the padding's `length_bytes` is measured from there.
+pub(crate) fn struct_start_ident() -> syn::Ident {
+    format_ident!("__struct_start_remaining_bits")
+}
+
+/// Generate statements which read `fields` (the fields after a trailing padding flag) when the
+/// flag variable `flag` is set: the number of padding bytes is taken from the last byte of the
+/// padded data, the fields are read from a view of the buffer which excludes the padding and the
+/// padding is then skipped.  The padded data ends `length_bytes` bytes from the start of the
+/// struct if that's given, or at the end of the buffer otherwise.  `trailing_const_reads` (the
+/// struct's trailing constants) are read from the view after the fields.  `adjust_message_start`
+/// is set when the message start variable needs to be adjusted for the view.
+pub(crate) fn generate_trailing_padding_read(
+    flag: &syn::Ident,
+    length_bytes: Option<&syn::Expr>,
+    fields: &[ParselyReadFieldData],
+    trailing_const_reads: TokenStream,
+    adjust_message_start: bool,
+) -> TokenStream {
+    let field_names = fields
+        .iter()
+        .map(|f| f.common.ident.as_variable_name())
+        .collect::<Vec<_>>();
+    let message_start = adjust_message_start.then(|| {
+        let message_start = message_start_ident();
+        quote! {
+            #[allow(unused_variables)]
+            let #message_start = #message_start - (remaining_bits - (region_bits - padding_bits));
+        }
+    });
+    let region_bits = match length_bytes {
+        Some(length_bytes) => {
+            let struct_start = struct_start_ident();
+            quote! {
+                {
+                    let length_bytes: usize = (#length_bytes).into_parsely_result().context("Length of the padded struct")?;
+                    let read_bits = #struct_start - remaining_bits;
+                    match (length_bytes * 8).checked_sub(read_bits) {
+                        Some(region_bits) if region_bits <= remaining_bits => region_bits,
+                        _ => bail!("Padded struct's length of {} bytes doesn't fit the {} bits read so far and {} bits remaining", length_bytes, read_bits, remaining_bits),
+                    }
+                }
+            }
+        }
+        None => quote! { remaining_bits },
+    };
+    let crate_name = get_crate_name();
+    quote! {
+        let (#(#field_names,)*) = {
+            let remaining_bits = buf.remaining_bits();
+            let region_bits = #region_bits;
+            // Only the padding count is peeked, and everything is then read from the buffer that
+            // 'peek_trailing_padding' gives back
+            let (padding_bits, mut peeked_buf) = if #flag {
+                ::#crate_name::parsely_read::peek_trailing_padding(&mut *buf, region_bits).context("Reading padding count")?
+            } else {
+                (0, ::#crate_name::Bits::from_static_bytes(&[]).chain(&mut *buf))
+            };
+            let buf = &mut peeked_buf;
+            let mut payload_buf = (&mut *buf).take_bits(region_bits - padding_bits);
+            let fields = {
+                let buf = &mut payload_buf;
+                #message_start
+
+                #(#fields)*
+                #trailing_const_reads
+
+                // Skip whatever the fields didn't read
+                buf.advance_bits(buf.remaining_bits());
+                (#(#field_names,)*)
+            };
+            buf.advance_bits(padding_bits);
+            fields
+        };
+    }
+}
+
+/// Generate statements which read a 'magic' byte sequence and check that it matches `magic`
+pub(crate) fn generate_magic_read(magic: &syn::LitByteStr) -> TokenStream {
+    let len = magic.value().len();
+    quote! {
+        {
+            let mut read_magic = [0u8; #len];
+            for byte in read_magic.iter_mut() {
+                *byte = buf.get_u8().context("Reading magic")?;
+            }
+            if read_magic != *#magic {
+                bail!("Magic value didn't match: expected {:02x?}, got {:02x?}", #magic, read_magic);
+            }
         }
     }
 }
+
+/// Generate statements which skip reserved bits, checking that they're zero if required
+pub(crate) fn generate_skip_bits_read(skip_bits: &SkipBits, description: &str) -> TokenStream {
+    let crate_name = get_crate_name();
+    let count = skip_bits.bits;
+    let must_be_zero = skip_bits.must_be_zero;
+    quote! {
+        ::#crate_name::reserved::skip_reserved_bits::<_, T>(buf, #count, #must_be_zero)
+            .with_context(|| format!("Skipping {}", #description))?;
+    }
+}
+
+/// Wrap `inner` (which reads the struct fields `fields`) so that the bits it reads are recorded,
+/// and then verify each checksum field against the checksum of the bits in its range.
+pub(crate) fn wrap_read_with_checksums(
+    fields: &[ParselyReadFieldData],
+    inner: TokenStream,
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    let field_names = fields
+        .iter()
+        .map(|f| f.common.ident.as_variable_name())
+        .collect::<Vec<_>>();
+    let struct_end = struct_end_position_ident();
+    let verifications = fields.iter().filter_map(|f| {
+        let checksum = f.common.checksum.as_ref()?;
+        let algorithm = &checksum.algorithm;
+        let range = checksum_range_tokens(&checksum.range, &f.common.ident);
+        let (field_start, field_end) = field_position_idents(&f.common.ident);
+        let field_variable_name = f.common.ident.as_variable_name();
+        let field_name_string = f.common.ident.as_friendly_string();
+        Some(quote! {
+            ::#crate_name::checksum::verify_checksum::<#algorithm, _>(
+                buf.recorded(),
+                #range,
+                #field_start..#field_end,
+                #field_name_string,
+                &#field_variable_name,
+            )?;
+        })
+    });
+    quote! {
+        let (#(#field_names,)*) = {
+            let mut recording_buf = ::#crate_name::checksum::RecordingBuf::new(&mut *buf);
+            let buf = &mut recording_buf;
+
+            #inner
+
+            #[allow(unused_variables)]
+            let #struct_end = buf.len_bits();
+            #(#verifications)*
+            (#(#field_names,)*)
+        };
+    }
+}
@@ -1,38 +1,124 @@
-use ::anyhow::anyhow;
+use ::anyhow::{anyhow, bail};
+use darling::ast;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
-use crate::{anyhow, get_crate_name, syn_helpers::MemberExts, ParselyReadReceiver, TypedFnArgList};
+use crate::{
+    anyhow,
+    code_gen::{
+        helpers::{check_message_alignment, generate_message_start},
+        parsely_common_variant_data::ParselyCommonVariantData,
+    },
+    get_crate_name,
+    model_types::{Alignment, Hooks, Validation, VariantId, VariantIdDecl},
+    syn_helpers::MemberExts,
+    Context, ParselyReadReceiver, TypedFnArgList,
+};
 
 use super::{
-    helpers::wrap_read_with_padding_handling, parsely_read_field_data::ParselyReadFieldData,
+    helpers::{generate_magic_read, wrap_read_with_padding_handling},
+    parsely_read_field_data::ParselyReadFieldData,
     parsely_read_variant_data::ParselyReadVariantData,
 };
 
+/// Describes where the value used to determine which variant of an enum to read comes from
+#[derive(Debug)]
+pub(crate) enum EnumKey {
+    /// A tag of the given type is read from the buffer
+    Read(syn::Type),
+    /// A tag of the given type is read from the buffer without consuming it, so the variant can
+    /// read it again
+    Peek(syn::Type),
+    /// The given expression (which can refer to the required context) is used, and nothing is
+    /// read from the buffer
+    Expr(syn::Expr),
+}
+
 /// A struct which represents all information needed for generating a `ParselyRead` implementation
 /// for a given struct.
 #[derive(Debug)]
 pub(crate) struct ParselyReadEnumData {
     pub(crate) ident: syn::Ident,
     pub(crate) required_context: TypedFnArgList,
-    pub(crate) alignment: Option<usize>,
-    pub(crate) key_type: syn::Type,
+    pub(crate) alignment: Option<Alignment>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) validate: Option<Validation>,
+    pub(crate) hooks: Hooks,
+    pub(crate) key: EnumKey,
+    /// Values that need to be passed as context when reading the tag
+    pub(crate) key_context: Option<Context>,
     pub(crate) variants: Vec<ParselyReadVariantData>,
 }
 
+/// Generate a read of an enum's key of type `key_type` from `buf`.  A tuple key is read one
+/// element at a time, so each element only needs to implement `ParselyRead` itself.
+fn generate_key_read(
+    key_type: &syn::Type,
+    buf: TokenStream,
+    key_context_values: &[syn::Expr],
+    context: &str,
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    if let syn::Type::Tuple(ref tuple) = key_type {
+        let element_reads = tuple.elems.iter().enumerate().map(|(idx, element_type)| {
+            quote! {
+                <#element_type as ::#crate_name::ParselyRead<_>>::read::<T>(#buf, ()).with_context(|| format!("{} (element {})", #context, #idx))?
+            }
+        });
+        quote! {
+            (#(#element_reads,)*)
+        }
+    } else {
+        quote! {
+            <#key_type as ::#crate_name::ParselyRead<_>>::read::<T>(#buf, (#(#key_context_values,)*)).with_context(|| #context)?
+        }
+    }
+}
+
 impl TryFrom<ParselyReadReceiver> for ParselyReadEnumData {
     type Error = anyhow::Error;
 
     fn try_from(value: ParselyReadReceiver) -> Result<Self, Self::Error> {
-        let key_type = value
-            .key_type
-            .ok_or(anyhow!("'key_type' attribute is required on enums"))?;
-        let variants = value
-            .data
-            .take_enum()
-            .ok_or(anyhow!("Not an enum"))?
+        let key = match (value.key_type, value.key) {
+            (Some(key_type), None) if value.peek_key => EnumKey::Peek(key_type),
+            (Some(key_type), None) => EnumKey::Read(key_type),
+            (_, Some(_)) if value.peek_key => bail!("'peek_key' can't be combined with 'key'"),
+            (None, Some(key)) => EnumKey::Expr(key),
+            (Some(_), Some(_)) => bail!(
+                "'key_type' and 'key' can't be combined when reading: to write a tag for an enum \
+                whose key isn't read from the buffer use #[parsely_write(key_type = ...)]"
+            ),
+            (None, None) => bail!("'key_type' or 'key' attribute is required on enums"),
+        };
+        if let EnumKey::Read(syn::Type::Tuple(_)) | EnumKey::Peek(syn::Type::Tuple(_)) = key {
+            if value.key_context.is_some() {
+                bail!("'key_context' can't be used with a tuple 'key_type'");
+            }
+        }
+        let variants = value.data.take_enum().ok_or(anyhow!("Not an enum"))?;
+        // Variants have implicit discriminants if the enum is fieldless or has a #[repr]
+        let implicit_discriminants = value.attrs.iter().any(|a| a.path().is_ident("repr"))
+            || variants.iter().all(|v| v.fields.is_empty());
+        let ids = VariantId::assign(
+            &value.ident,
+            variants.iter().map(|v| VariantIdDecl {
+                ident: &v.ident,
+                id: v.common.id.clone(),
+                discriminant: v.discriminant.clone(),
+                holds_key: v.common.unknown && !v.fields.is_empty(),
+            }),
+            implicit_discriminants,
+        )?;
+        let variants = variants
             .into_iter()
-            .map(|v| {
+            .zip(ids)
+            .map(|(v, id)| {
+                if v.common.unknown && (v.fields.len() > 1 || v.fields.style == ast::Style::Struct) {
+                    bail!(
+                        "Unknown variant '{}' can only have a single unnamed field (to hold the key)",
+                        v.ident
+                    );
+                }
                 let data_fields = v
                     .fields
                     .into_iter()
@@ -45,21 +131,56 @@ impl TryFrom<ParselyReadReceiver> for ParselyReadEnumData {
                         ParselyReadFieldData::from_receiver(ident, field)
                     })
                     .collect::<Vec<_>>();
-                ParselyReadVariantData {
-                    enum_name: value.ident.clone(),
-                    ident: v.ident,
-                    id: v.id,
-                    discriminant: v.discriminant,
+                Ok(ParselyReadVariantData {
+                    common: ParselyCommonVariantData::from_receiver(
+                        value.ident.clone(),
+                        v.ident,
+                        v.common,
+                    )?,
+                    id,
                     fields: data_fields,
-                }
+                })
             })
-            .collect::<Vec<_>>();
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if variants.iter().filter(|v| v.common.unknown).count() > 1 {
+            bail!("Only one variant can be marked 'unknown'");
+        }
+        check_message_alignment(&value.ident, value.alignment.as_ref())?;
+        if variants
+            .iter()
+            .flat_map(|v| v.fields.iter())
+            .any(|f| f.common.trailing_padding.is_some())
+        {
+            bail!("'trailing_padding' can only be used on struct fields");
+        }
+        if variants
+            .iter()
+            .flat_map(|v| v.fields.iter())
+            .any(|f| f.common.checksum.is_some())
+        {
+            bail!("'checksum' can only be used on struct fields");
+        }
+        if value.pad_to_bytes.is_some() {
+            bail!("'pad_to_bytes' can't be used on enums: use 'length_bytes' on their variants");
+        }
+        if !value.trailing_const_field.is_empty() {
+            bail!("'trailing_const_field' can only be used on structs");
+        }
 
         Ok(ParselyReadEnumData {
             ident: value.ident,
-            key_type,
+            key,
+            key_context: value.key_context,
             required_context: value.required_context,
             alignment: value.alignment,
+            magic: value.magic,
+            validate: value.validate,
+            hooks: Hooks {
+                pre_read: value.pre_read,
+                post_read: value.post_read,
+                pre_write: value.pre_write,
+                post_write: value.post_write,
+            },
             variants,
         })
     }
@@ -73,18 +194,59 @@ impl ToTokens for ParselyReadEnumData {
         let (context_variables, context_types) =
             (self.required_context.names(), self.required_context.types());
 
-        let match_type = &self.key_type;
+        let key_context_values = self
+            .key_context
+            .as_ref()
+            .map(|c| c.expressions(&format!("Key context for enum '{enum_name_string}'")))
+            .unwrap_or_default();
+        let match_value = match self.key {
+            EnumKey::Read(ref key_type) => {
+                let key_read = generate_key_read(
+                    key_type,
+                    quote! { buf },
+                    &key_context_values,
+                    &format!("Tag for enum '{enum_name_string}'"),
+                );
+                quote! { let match_value = #key_read; }
+            }
+            // Read the tag speculatively, and then read the variant from a buffer which gives the
+            // tag's bits again.
+            EnumKey::Peek(ref key_type) => {
+                let key_read = generate_key_read(
+                    key_type,
+                    quote! { peek_buf },
+                    &key_context_values,
+                    &format!("Peeking tag for enum '{enum_name_string}'"),
+                );
+                quote! {
+                    let (match_value, mut peeked_buf) = ::#crate_name::parsely_read::peek(&mut *buf, |peek_buf| Ok(#key_read))?;
+                    let buf = &mut peeked_buf;
+                }
+            }
+            EnumKey::Expr(ref key) => quote! {
+                let match_value = (#key).into_parsely_result().with_context(|| format!("Key for enum '{}'", #enum_name_string))?;
+            },
+        };
 
-        let match_arms = &self.variants;
+        let match_arms = self.variants.iter().filter(|v| !v.common.unknown);
+        // The unknown variant (if there is one) catches any value that the other arms didn't
+        let fallback_arm = match self.variants.iter().find(|v| v.common.unknown) {
+            Some(unknown) => quote! { #unknown },
+            None => quote! {
+                _ => ParselyResult::<_>::Err(anyhow!("No arms matched value")),
+            },
+        };
+        let magic_read = self.magic.as_ref().map(generate_magic_read);
         let body = quote! {
-            let match_value = <#match_type as ::#crate_name::ParselyRead<_>>::read::<T>(buf, ()).with_context(|| format!("Tag for enum '{}'", #enum_name_string))?;
-            match match_value {
+            #magic_read
+            #match_value
+            let value: ::#crate_name::ParselyResult<Self> = match match_value {
                 #(#match_arms)*
-                _ => ParselyResult::<_>::Err(anyhow!("No arms matched value")),
-            }
+                #fallback_arm
+            };
         };
 
-        let body = if let Some(alignment) = self.alignment {
+        let body = if let Some(ref alignment) = self.alignment {
             wrap_read_with_padding_handling(
                 &syn::Member::Named(self.ident.clone()),
                 alignment,
@@ -94,6 +256,35 @@ impl ToTokens for ParselyReadEnumData {
             body
         };
 
+        let message_start = generate_message_start(
+            self.variants.iter().flat_map(|v| {
+                v.common
+                    .alignment
+                    .iter()
+                    .chain(v.fields.iter().filter_map(|f| f.common.alignment.as_ref()))
+            }),
+            quote! { buf.remaining_bits() },
+        );
+
+        let enum_description = format!("'{enum_name_string}'");
+        let pre_read = self.hooks.pre_read_tokens(&enum_description);
+        let post_read = self.hooks.post_read_tokens(&enum_description);
+        let validation = self
+            .validate
+            .as_ref()
+            .map(|validate| validate.to_check_tokens(enum_name, quote! { &value }));
+        let result = if post_read.is_some() || validation.is_some() {
+            quote! {
+                #[allow(unused_mut)]
+                let mut value = value?;
+                #post_read
+                #validation
+                Ok(value)
+            }
+        } else {
+            quote! { value }
+        };
+
         // TODO: should the enum id be able to be read from the buffer?  we could have it support
         // being an expr that returns a result or not, like other things.  so it could be
         // "buf.get_u8()"
@@ -101,7 +292,10 @@ impl ToTokens for ParselyReadEnumData {
             impl<B: BitBuf> ::#crate_name::ParselyRead<B> for #enum_name {
                 type Ctx = (#(#context_types,)*);
                 fn read<T: ::#crate_name::ByteOrder>(buf: &mut B, (#(#context_variables,)*): (#(#context_types,)*)) -> ::#crate_name::ParselyResult<Self> {
+                    #pre_read
+                    #message_start
                     #body
+                    #result
                 }
             }
         });
@@ -2,14 +2,19 @@ use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
 use crate::{
-    code_gen::{helpers::wrap_in_optional, parsely_common_field_data::ParselyCommonFieldData},
+    code_gen::{
+        helpers::{generate_position_markers, wrap_in_optional},
+        parsely_common_field_data::ParselyCommonFieldData,
+    },
+    get_crate_name,
     model_types::CollectionLimit,
     syn_helpers::MemberExts,
     ParselyReadFieldReceiver, TypeExts,
 };
 
 use super::helpers::{
-    generate_collection_read, generate_plain_read, wrap_read_with_padding_handling,
+    generate_collection_read, generate_plain_read, generate_reader_read, generate_skip_bits_read,
+    wrap_read_with_padding_handling, wrap_read_with_size_padding,
 };
 
 /// A struct which represents all information needed for generating logic to read a field from a
@@ -25,6 +30,8 @@ pub struct ParselyReadFieldData {
     pub(crate) assign_from: Option<syn::Expr>,
     /// 'when' is required when there's an optional field
     pub(crate) when: Option<syn::Expr>,
+    /// A function which reads the value instead of its type's `ParselyRead` implementation
+    pub(crate) reader: Option<syn::Path>,
 }
 
 impl ParselyReadFieldData {
@@ -32,7 +39,21 @@ impl ParselyReadFieldData {
         field_ident: syn::Member,
         receiver: ParselyReadFieldReceiver,
     ) -> Self {
-        let collection_limit = if receiver.ty.is_collection() {
+        if receiver.common.skip
+            && (receiver.count.is_some()
+                || receiver.while_pred.is_some()
+                || receiver.assign_from.is_some()
+                || receiver.when.is_some()
+                || receiver.reader.is_some())
+        {
+            panic!(
+                "Field '{}': 'skip' can't be combined with attributes for reading the field",
+                field_ident.as_friendly_string()
+            );
+        }
+        let collection_limit = if receiver.common.skip {
+            None
+        } else if receiver.ty.is_collection() {
             if let Some(count) = receiver.count {
                 Some(CollectionLimit::Count(count))
             } else if let Some(while_pred) = receiver.while_pred {
@@ -43,7 +64,7 @@ impl ParselyReadFieldData {
         } else {
             None
         };
-        let when = if receiver.ty.is_option() {
+        let when = if receiver.ty.is_option() && !receiver.common.skip {
             Some(
                 receiver
                     .when
@@ -52,19 +73,25 @@ impl ParselyReadFieldData {
         } else {
             None
         };
-        let common = ParselyCommonFieldData {
-            ident: field_ident,
-            ty: receiver.ty,
-            assertion: receiver.common.assertion,
-            context: receiver.common.context,
-            map: receiver.common.map,
-            alignment: receiver.common.alignment,
-        };
+        if receiver.reader.is_some()
+            && (receiver.assign_from.is_some()
+                || receiver.common.as_type.is_some()
+                || receiver.common.steps.field.has_map()
+                || receiver.common.steps.elem.has_map())
+        {
+            panic!(
+                "Field '{}': 'reader' can't be combined with 'assign_from', 'as' or 'map'",
+                field_ident.as_friendly_string()
+            );
+        }
+        let common =
+            ParselyCommonFieldData::from_receiver(field_ident, receiver.ty, receiver.common);
         Self {
             common,
             collection_limit,
             assign_from: receiver.assign_from,
             when,
+            reader: receiver.reader,
         }
     }
 }
@@ -76,56 +103,86 @@ impl ToTokens for ParselyReadFieldData {
     /// The attributes set in the [`ParselyReadFieldData`] all shape the logic necessary in order to
     /// properly parse this field.  Roughly, the processing is as follows:
     ///
+    /// 0. Check if a 'skip' attribute is set.  If so, the field isn't in the buffer and is just
+    ///    assigned its default value.
     /// 1. Check if an 'assign_from' attribute is set.  If so, we don't read from the buffer at all and
     ///    instead just assign the field to the result of the given expression.
-    /// 2. Check if a 'map' attribute is set.  If so, we'll read a value as a different type and then
-    ///    pass it t othe map function to arrive at the final type and assign it to the field.
+    /// 2. Check if any 'map' attributes are set.  If so, we'll read a value as a different type
+    ///    (inferred from the first map function) which the maps will convert to the final type.
     /// 3. Check if the field is a collection.  If so, some kind of accompanying 'limit' attribute is
     ///    required: either a 'count' attribute or a `while_pred` attribute that defines how many
-    ///    elements should be read.
+    ///    elements should be read.  Any 'elem_map' and 'elem_assertion' attributes are applied to
+    ///    each element as it's read.  If a 'reader' function is set, it reads each element.
     /// 4. If none of the above are the case, do a 'plain' read where we just read the type directly
-    ///    from the buffer.
-    /// 5. If an 'assertion' attribute is present then generate code to assert on the read value using
-    ///    the given assertion function or closure.
+    ///    from the buffer, or read it with the 'reader' function if one is set.
+    /// 5. Apply any 'assertion' and 'map' attributes to the read value in the order they were
+    ///    declared: an assertion sees the value as it is at that point in the chain.
     /// 6. After the code to perform the read has been generated, we check if the field is an option
     ///    type.  If so, a 'when' attribute is required.  This is an expression that determines when the
     ///    read should actually be done.
-    /// 7. Finally, if an 'alignment' attribute is present, code is added to detect and consume any
+    /// 7. If a 'pad_to_bytes' attribute is present, code is added to consume whatever the read
+    ///    didn't up to the given size.
+    /// 8. Finally, if an 'alignment' attribute is present, code is added to detect and consume any
     ///    padding after the read.
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        let field_variable_name = self.common.ident.as_variable_name();
+        let (checksum_start, checksum_end) = generate_position_markers(&self.common);
+        if let Some(ref default) = self.common.skip {
+            // The field isn't in the buffer, so it's just given its value
+            let ty = &self.common.ty;
+            tokens.extend(quote! {
+                #checksum_start
+                let #field_variable_name: #ty = #default;
+                #checksum_end
+            });
+            return;
+        }
+
         let mut output = TokenStream::new();
         if let Some(ref assign_expr) = self.assign_from {
             output.extend(quote! {
                 ParselyResult::<_>::Ok(#assign_expr)
             });
-        } else if let Some(ref map_expr) = self.common.map {
-            map_expr.to_read_map_tokens(&self.common.ident, &mut output);
+        } else if self.common.steps.has_map() {
+            output.extend(raw_read_tokens(
+                &self.common.ident,
+                &self.common.context_values(),
+            ));
         } else if self.common.ty.is_collection() {
             // We've ensure collection_limit is set in this case elswhere.
             let limit = self.collection_limit.as_ref().unwrap();
             let read_type = self.common.buffer_type();
-            output.extend(generate_collection_read(
-                limit,
-                read_type,
-                &self.common.context_values(),
-            ));
+            let context_values = self.common.context_values();
+            let mut element_read = if self.common.elem_steps.has_map() {
+                raw_read_tokens(&self.common.ident, &context_values)
+            } else if let Some(ref reader) = self.reader {
+                generate_reader_read(reader, &context_values)
+            } else {
+                generate_plain_read(read_type, self.common.as_type.as_ref(), &context_values)
+            };
+            self.common
+                .elem_steps
+                .to_read_tokens(&self.common.ident, &mut element_read);
+            output.extend(generate_collection_read(limit, read_type, element_read));
+        } else if let Some(ref reader) = self.reader {
+            output.extend(generate_reader_read(reader, &self.common.context_values()));
         } else {
             output.extend(generate_plain_read(
                 self.common.buffer_type(),
+                self.common.as_type.as_ref(),
                 &self.common.context_values(),
             ));
         }
 
-        if let Some(ref assertion) = self.common.assertion {
-            assertion
-                .to_read_assertion_tokens(&self.common.ident.as_friendly_string(), &mut output);
-        }
+        self.common
+            .steps
+            .to_read_tokens(&self.common.ident, &mut output);
         let error_context = format!("Reading field '{}'", self.common.ident.as_friendly_string());
         output.extend(quote! {
             .with_context(|| #error_context)?
         });
 
-        output = if self.common.ty.is_option() && self.common.map.is_none() {
+        output = if self.common.ty.is_option() && !self.common.steps.has_map() {
             // We've ensured 'when' is set in this case elsehwere
             let when_expr = self.when.as_ref().unwrap();
             wrap_in_optional(when_expr, output)
@@ -133,15 +190,73 @@ impl ToTokens for ParselyReadFieldData {
             output
         };
 
-        output = if let Some(alignment) = self.common.alignment {
-            wrap_read_with_padding_handling(&self.common.ident, alignment, output)
+        let mut read_with_padding = None;
+        if let Some(ref size_bytes) = self.common.pad_to_bytes {
+            read_with_padding = Some(wrap_read_with_size_padding(
+                &self.common.ident,
+                size_bytes,
+                quote! { let value = #output; },
+            ));
+        }
+        if let Some(ref alignment) = self.common.alignment {
+            read_with_padding = Some(wrap_read_with_padding_handling(
+                &self.common.ident,
+                alignment,
+                read_with_padding.unwrap_or(quote! { let value = #output; }),
+            ));
+        }
+        output = if let Some(read_with_padding) = read_with_padding {
+            quote! {
+                {
+                    #read_with_padding
+                    value
+                }
+            }
         } else {
             output
         };
 
-        let field_variable_name = self.common.ident.as_variable_name();
+        let field_description = format!("field '{}'", self.common.ident.as_friendly_string());
+        tokens.extend(self.common.hooks.pre_read_tokens(&field_description));
+        if let Some(ref skip_bits) = self.common.skip_bits {
+            tokens.extend(generate_skip_bits_read(
+                skip_bits,
+                &format!(
+                    "reserved bits before field '{}'",
+                    self.common.ident.as_friendly_string()
+                ),
+            ));
+        }
+        tokens.extend(self.common.const_fields.to_read_tokens(&format!(
+            "constant before field '{}'",
+            self.common.ident.as_friendly_string()
+        )));
+        // The field can be modified by its post_read hook
+        let post_read = self.common.hooks.post_read_tokens(&field_description);
+        let binding = if post_read.is_some() {
+            quote! {
+                #[allow(unused_mut)]
+                let mut #field_variable_name
+            }
+        } else {
+            quote! { let #field_variable_name }
+        };
         tokens.extend(quote! {
-            let #field_variable_name = #output;
+            #checksum_start
+            #binding = #output;
+            #checksum_end
+            #post_read
         })
     }
 }
+
+/// Generate a read of a 'raw' value for a field with a map, whose type will be inferred from the
+/// first map function.
+fn raw_read_tokens(field_ident: &syn::Member, context_values: &[syn::Expr]) -> TokenStream {
+    let crate_name = get_crate_name();
+    let field_name_string = field_ident.as_friendly_string();
+    quote! {
+        ::#crate_name::ParselyRead::read::<T>(buf, (#(#context_values,)*))
+            .with_context(|| format!("Reading raw value for field '{}'", #field_name_string))
+    }
+}
@@ -1,12 +1,23 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
-use crate::syn_helpers::MemberExts;
-use crate::{get_crate_name, ParselyReadReceiver, TypedFnArgList};
+use crate::code_gen::helpers::{
+    check_message_alignment, checksum_references, generate_message_start, mark_field_positions,
+};
+use crate::syn_helpers::{refers_to, MemberExts};
+use crate::{
+    get_crate_name,
+    model_types::{Alignment, ConstFields, Hooks, Validation},
+    ParselyReadReceiver, TypedFnArgList,
+};
 
 use super::{
-    helpers::wrap_read_with_padding_handling, parsely_read_field_data::ParselyReadFieldData,
+    helpers::{
+        generate_magic_read, generate_trailing_padding_read, struct_start_ident,
+        wrap_read_with_checksums, wrap_read_with_padding_handling, wrap_read_with_size_padding,
+    },
+    parsely_read_field_data::ParselyReadFieldData,
 };
 
 /// A struct which represents all information needed for generating a `ParselyRead` implementation
@@ -15,7 +26,14 @@ pub(crate) struct ParselyReadStructData {
     pub(crate) ident: syn::Ident,
     pub(crate) style: darling::ast::Style,
     pub(crate) required_context: TypedFnArgList,
-    pub(crate) alignment: Option<usize>,
+    pub(crate) alignment: Option<Alignment>,
+    pub(crate) pad_to_bytes: Option<syn::Expr>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) trailing_const_fields: ConstFields,
+    pub(crate) validate: Option<Validation>,
+    pub(crate) hooks: Hooks,
+    /// Whether any of the fields is a checksum
+    pub(crate) has_checksums: bool,
     pub(crate) fields: Vec<ParselyReadFieldData>,
 }
 
@@ -28,7 +46,7 @@ impl TryFrom<ParselyReadReceiver> for ParselyReadStructData {
             .take_struct()
             .ok_or(anyhow!("Not a struct"))?
             .split();
-        let data_fields = struct_receiver_fields
+        let mut data_fields = struct_receiver_fields
             .into_iter()
             .enumerate()
             .map(|(field_index, field)| {
@@ -37,11 +55,37 @@ impl TryFrom<ParselyReadReceiver> for ParselyReadStructData {
                 ParselyReadFieldData::from_receiver(ident, field)
             })
             .collect::<Vec<_>>();
+        check_message_alignment(&value.ident, value.alignment.as_ref())?;
+        if data_fields
+            .iter()
+            .filter(|f| f.common.trailing_padding.is_some())
+            .count()
+            > 1
+        {
+            bail!("Only one field can be marked 'trailing_padding'");
+        }
+        let checksum_references = checksum_references(data_fields.iter().map(|f| &f.common));
+        let has_checksums = mark_field_positions(
+            &value.ident,
+            data_fields.iter_mut().map(|f| &mut f.common),
+            &checksum_references,
+        )?;
         Ok(ParselyReadStructData {
             ident: value.ident,
             style,
             required_context: value.required_context,
             alignment: value.alignment,
+            pad_to_bytes: value.pad_to_bytes,
+            magic: value.magic,
+            trailing_const_fields: value.trailing_const_field,
+            validate: value.validate,
+            hooks: Hooks {
+                pre_read: value.pre_read,
+                post_read: value.post_read,
+                pre_write: value.pre_write,
+                post_write: value.post_write,
+            },
+            has_checksums,
             fields: data_fields,
         })
     }
@@ -57,11 +101,81 @@ impl ToTokens for ParselyReadStructData {
             (self.required_context.names(), self.required_context.types());
 
         let fields = &self.fields;
-        let field_reads = quote! {
-            #(#fields)*
+        let message_start = generate_message_start(
+            fields.iter().filter_map(|f| f.common.alignment.as_ref()),
+            quote! { buf.remaining_bits() },
+        );
+        let magic_read = self.magic.as_ref().map(generate_magic_read);
+        let trailing_const_reads = self
+            .trailing_const_fields
+            .to_read_tokens(&format!("constant after the fields of '{struct_name}'"));
+        let field_reads = match fields
+            .iter()
+            .position(|f| f.common.trailing_padding.is_some())
+        {
+            Some(flag_index) => {
+                let (before_padding, after_padding) = fields.split_at(flag_index + 1);
+                let flag = &fields[flag_index].common;
+                let length_bytes = flag
+                    .trailing_padding
+                    .as_ref()
+                    .and_then(|padding| padding.length_bytes.as_ref());
+                let struct_start = length_bytes.map(|_| {
+                    let struct_start = struct_start_ident();
+                    quote! { let #struct_start = buf.remaining_bits(); }
+                });
+                // The fields that the length refers to (like a length field after the flag) are
+                // read before the padding is looked for
+                let bounded_start = length_bytes
+                    .and_then(|length_bytes| {
+                        after_padding.iter().rposition(|f| {
+                            refers_to(
+                                length_bytes.to_token_stream(),
+                                &f.common.ident.as_variable_name(),
+                            )
+                        })
+                    })
+                    .map_or(0, |index| index + 1);
+                let (unbounded, bounded) = after_padding.split_at(bounded_start);
+                let padding_read = generate_trailing_padding_read(
+                    &flag.ident.as_variable_name(),
+                    length_bytes,
+                    bounded,
+                    trailing_const_reads,
+                    message_start.is_some(),
+                );
+                quote! {
+                    #struct_start
+                    #magic_read
+                    #(#before_padding)*
+                    #(#unbounded)*
+                    #padding_read
+                }
+            }
+            None => quote! {
+                #magic_read
+                #(#fields)*
+                #trailing_const_reads
+            },
+        };
+
+        let field_reads = if self.has_checksums {
+            wrap_read_with_checksums(fields, field_reads)
+        } else {
+            field_reads
+        };
+
+        let field_reads = if let Some(ref size_bytes) = self.pad_to_bytes {
+            wrap_read_with_size_padding(
+                &syn::Member::Named(self.ident.clone()),
+                size_bytes,
+                field_reads,
+            )
+        } else {
+            field_reads
         };
 
-        let body = if let Some(alignment) = self.alignment {
+        let body = if let Some(ref alignment) = self.alignment {
             wrap_read_with_padding_handling(
                 &syn::Member::Named(self.ident.clone()),
                 alignment,
@@ -76,29 +190,41 @@ impl ToTokens for ParselyReadStructData {
             .map(|f| f.common.ident.as_variable_name().to_owned())
             .collect::<Vec<_>>();
 
-        // TODO: reduce the duplicated code here
-        if self.style.is_struct() {
-            tokens.extend(quote! {
-                impl<B: BitBuf> ::#crate_name::ParselyRead<B> for #struct_name {
-                    type Ctx = (#(#context_types,)*);
-                    fn read<T: ::#crate_name::ByteOrder>(buf: &mut B, (#(#context_variables,)*): (#(#context_types,)*)) -> ::#crate_name::ParselyResult<Self> {
-                        #body
-
-                        Ok(Self { #(#field_names,)* })
-                    }
-                }
-            })
+        let value = match self.style {
+            darling::ast::Style::Struct => quote! { Self { #(#field_names,)* } },
+            darling::ast::Style::Tuple => quote! { Self(#(#field_names,)* ) },
+            darling::ast::Style::Unit => quote! { Self },
+        };
+        let struct_description = format!("'{struct_name}'");
+        let pre_read = self.hooks.pre_read_tokens(&struct_description);
+        let post_read = self.hooks.post_read_tokens(&struct_description);
+        let validation = self
+            .validate
+            .as_ref()
+            .map(|validate| validate.to_check_tokens(struct_name, quote! { &value }));
+        let result = if post_read.is_some() || validation.is_some() {
+            quote! {
+                #[allow(unused_mut)]
+                let mut value = #value;
+                #post_read
+                #validation
+                Ok(value)
+            }
         } else {
-            tokens.extend(quote! {
-                impl<B: BitBuf> ::#crate_name::ParselyRead<B> for #struct_name {
-                    type Ctx = (#(#context_types,)*);
-                    fn read<T: ::#crate_name::ByteOrder>(buf: &mut B, (#(#context_variables,)*): (#(#context_types,)*)) -> ::#crate_name::ParselyResult<Self> {
-                        #body
+            quote! { Ok(#value) }
+        };
+
+        tokens.extend(quote! {
+            impl<B: BitBuf> ::#crate_name::ParselyRead<B> for #struct_name {
+                type Ctx = (#(#context_types,)*);
+                fn read<T: ::#crate_name::ByteOrder>(buf: &mut B, (#(#context_variables,)*): (#(#context_types,)*)) -> ::#crate_name::ParselyResult<Self> {
+                    #pre_read
+                    #message_start
+                    #body
 
-                        Ok(Self(#(#field_names,)* ))
-                    }
+                    #result
                 }
-            })
-        }
+            }
+        })
     }
 }
@@ -1,15 +1,21 @@
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
-use super::parsely_read_field_data::ParselyReadFieldData;
-use crate::syn_helpers::MemberExts;
+use super::{
+    helpers::wrap_read_with_padding_handling, parsely_read_field_data::ParselyReadFieldData,
+};
+use crate::{
+    code_gen::parsely_common_variant_data::ParselyCommonVariantData, model_types::VariantId,
+    syn_helpers::MemberExts,
+};
 
 #[derive(Debug)]
 pub(crate) struct ParselyReadVariantData {
-    pub(crate) enum_name: syn::Ident,
-    pub(crate) ident: syn::Ident,
-    pub(crate) id: syn::Expr,
-    pub(crate) discriminant: Option<syn::Expr>,
+    pub(crate) common: ParselyCommonVariantData,
+    /// This variant's id, which only an unknown variant that holds the key doesn't have
+    pub(crate) id: Option<VariantId>,
+    /// For an unknown variant, this is the field (if any) that the key is assigned to rather than
+    /// it being read from the buffer.
     pub(crate) fields: Vec<ParselyReadFieldData>,
 }
 
@@ -24,51 +30,90 @@ impl ParselyReadVariantData {
 
 impl ToTokens for ParselyReadVariantData {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let arm_expr = &self.id;
-        let arm_body = if let Some(ref discriminant) = self.discriminant {
-            quote! {
-                #discriminant
-            }
-        } else {
-            let fields = &self.fields;
-            quote! {
-                #(#fields)*
-
-
-            }
-        };
+        let enum_name = &self.common.enum_name;
+        let variant_name = &self.common.ident;
+        let variant_name_string = self.common.friendly_name();
         let field_names = self
             .fields
             .iter()
             .map(|f| f.common.ident.as_variable_name().to_owned())
             .collect::<Vec<_>>();
-        let enum_name = &self.enum_name;
-        let variant_name = &self.ident;
-        // TODO: don't think we're handling discriminant correctly here
-        if self.fields.is_empty() {
-            tokens.extend(quote! {
-                #arm_expr => {
-                    #arm_body
+        if self.common.unknown {
+            // An unknown variant holds the key itself (if anything), so nothing else is read
+            let arm = match field_names.first() {
+                Some(field_name) => quote! {
+                    #field_name => Ok(#enum_name::#variant_name(#field_name)),
+                },
+                None => quote! {
+                    _ => Ok(#enum_name::#variant_name),
+                },
+            };
+            tokens.extend(arm);
+            return;
+        }
 
-                    Ok(#enum_name::#variant_name)
-                }
-            })
-        } else if self.named_fields() {
-            tokens.extend(quote! {
-                #arm_expr => {
-                    #arm_body
+        let fields = &self.fields;
+        let mut field_reads = quote! {
+            #(#fields)*
+        };
+        if let Some(ref length) = self.common.length_bytes {
+            // Read the fields from a view of the buffer that's limited to the variant's length
+            field_reads = quote! {
+                let (#(#field_names,)*) = {
+                    let variant_length: usize = (#length).into_parsely_result().with_context(|| format!("Length of variant '{}'", #variant_name_string))?;
+                    if buf.remaining_bytes() < variant_length {
+                        bail!("Variant '{}' has a length of {} bytes but only {} remain", #variant_name_string, variant_length, buf.remaining_bytes());
+                    }
+                    let mut variant_buf = (&mut *buf).take_bytes(variant_length);
+                    let buf = &mut variant_buf;
 
-                    Ok(#enum_name::#variant_name { #(#field_names,)* })
-                }
-            })
-        } else {
-            tokens.extend(quote! {
-                #arm_expr => {
-                    #arm_body
+                    #field_reads
 
-                    Ok(#enum_name::#variant_name(#(#field_names,)* ))
-                }
-            })
+                    // Skip whatever the fields didn't read
+                    buf.advance_bits(buf.remaining_bits());
+                    (#(#field_names,)*)
+                };
+            };
         }
+        if let Some(ref alignment) = self.common.alignment {
+            field_reads = wrap_read_with_padding_handling(
+                &syn::Member::Named(variant_name.clone()),
+                alignment,
+                field_reads,
+            );
+        }
+        let context_bindings = self.common.context_bindings(Some(quote! { &match_value }));
+
+        let value = if self.fields.is_empty() {
+            quote! { #enum_name::#variant_name }
+        } else if self.named_fields() {
+            quote! { #enum_name::#variant_name { #(#field_names,)* } }
+        } else {
+            quote! { #enum_name::#variant_name(#(#field_names,)* ) }
+        };
+        let result = if self.common.assertion.is_some() {
+            let assertion = self.common.assertion_tokens(quote! { &value });
+            quote! {
+                let value = #value;
+                #assertion
+                Ok(value)
+            }
+        } else {
+            quote! { Ok(#value) }
+        };
+
+        let arm_expr = self
+            .id
+            .as_ref()
+            .expect("Only an unknown variant that holds the key can be without an id")
+            .to_match_arm_tokens();
+        tokens.extend(quote! {
+            #arm_expr => {
+                #context_bindings
+                #field_reads
+
+                #result
+            }
+        })
     }
 }
@@ -1,26 +1,122 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use crate::syn_helpers::MemberExts;
+use crate::{
+    code_gen::{
+        helpers::{
+            checksum_range_tokens, field_position_idents, message_start_ident,
+            struct_end_position_ident,
+        },
+        write::parsely_write_field_data::ParselyWriteFieldData,
+    },
+    get_crate_name,
+    model_types::{Alignment, SkipBits},
+    syn_helpers::MemberExts,
+};
 
 pub(crate) fn wrap_write_with_padding_handling(
     element_ident: &syn::Member,
-    alignment: usize,
+    alignment: &Alignment,
     inner: TokenStream,
 ) -> TokenStream {
-    let bytes_written_before_ident = format_ident!(
-        "__bytes_written_before_{}_write",
+    let crate_name = get_crate_name();
+    let bits_remaining_before_ident = format_ident!(
+        "__bits_remaining_before_{}_write",
         element_ident.as_friendly_string()
     );
+    let origin_offset = alignment
+        .origin_offset_tokens(&bits_remaining_before_ident, &message_start_ident())
+        .map(|origin_offset| quote! { + #origin_offset });
+    let alignment_bits = alignment.bits;
+    let pad = alignment.pad_tokens();
+
+    quote! {
+        let #bits_remaining_before_ident = buf.remaining_mut_bits();
 
+        #inner
+
+        {
+            let pad: u8 = #pad;
+            let offset = #bits_remaining_before_ident - buf.remaining_mut_bits() #origin_offset;
+            let padding_bits = (#alignment_bits - offset % #alignment_bits) % #alignment_bits;
+            // The pad value is repeated over each byte from the alignment's origin, most
+            // significant bit first, so each padding bit takes the pad bit at its position in its
+            // byte
+            for i in offset..offset + padding_bits {
+                buf.put_u1(::#crate_name::u1::new((pad >> (7 - i % 8)) & 1)).context("adding padding")?;
+            }
+        }
+    }
+}
+
+/// Wrap `inner` so that a total of `size_bytes` bytes are written for `element_ident`: whatever
+/// `inner` didn't write is filled with zeros, and writing fails if it wrote more than that.
+pub(crate) fn wrap_write_with_size_padding(
+    element_ident: &syn::Member,
+    size_bytes: &syn::Expr,
+    inner: TokenStream,
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    let element_name = element_ident.as_friendly_string();
+    let bits_remaining_before_ident =
+        format_ident!("__bits_remaining_before_{}_sized_write", element_name);
     quote! {
-        let #bytes_written_before_ident = buf.remaining_mut_bytes();
+        let #bits_remaining_before_ident = buf.remaining_mut_bits();
 
         #inner
 
-        while (#bytes_written_before_ident - buf.remaining_mut_bytes()) % #alignment != 0 {
-            buf.put_u8(0).context("adding padding")?;
+        {
+            let size_bytes: usize = (#size_bytes).into_parsely_result().with_context(|| format!("Size of '{}'", #element_name))?;
+            let bits_written = #bits_remaining_before_ident - buf.remaining_mut_bits();
+            if bits_written > size_bytes * 8 {
+                bail!("'{}' wrote {} bits, more than its size of {} bytes", #element_name, bits_written, size_bytes);
+            }
+            for _ in bits_written..size_bytes * 8 {
+                buf.put_u1(::#crate_name::u1::new(0)).context("adding padding")?;
+            }
+        }
+    }
+}
+
+/// The variable which holds the position of a trailing padding flag in the buffer it's written to,
+/// so that it can be set once the amount of padding is known.
+pub(crate) fn padding_flag_position_ident() -> syn::Ident {
+    format_ident!("__padding_flag_position")
+}
+
+/// Wrap `inner` (which writes a struct's fields, including a trailing padding flag) so that it
+/// writes to a separate buffer, which is then padded with trailing padding to a multiple of
+/// `alignment` bytes.  The flag is set according to whether any padding was added and the result
+/// is written to the buffer.  `message_start` is the statement which records the start of the
+/// message, if any is needed.
+pub(crate) fn wrap_write_with_trailing_padding(
+    alignment: usize,
+    message_start: Option<TokenStream>,
+    inner: TokenStream,
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    let flag_position = padding_flag_position_ident();
+    quote! {
+        let mut padded_buf = ::#crate_name::BitsMut::new();
+        {
+            let buf = &mut padded_buf;
+            #message_start
+
+            #inner
+
+            if buf.len_bits() % 8 != 0 {
+                bail!("Trailing padding can only be added after a whole number of bytes");
+            }
+            let padding_bytes = (#alignment - buf.len_bytes() % #alignment) % #alignment;
+            buf.set(#flag_position, padding_bytes > 0);
+            if padding_bytes > 0 {
+                for _ in 1..padding_bytes {
+                    buf.put_u8(0).context("Writing padding")?;
+                }
+                buf.put_u8(padding_bytes as u8).context("Writing padding count")?;
+            }
         }
+        buf.try_put_bit_slice(&padded_buf).context("Writing padded data")?;
     }
 }
 
@@ -29,3 +125,98 @@ pub(crate) enum ParentType {
     Struct,
     Enum,
 }
+
+/// Generate statements which write a 'magic' byte sequence
+pub(crate) fn generate_magic_write(magic: &syn::LitByteStr) -> TokenStream {
+    quote! {
+        for byte in #magic {
+            buf.put_u8(*byte).context("Writing magic")?;
+        }
+    }
+}
+
+/// Generate statements which write zeroed reserved bits
+pub(crate) fn generate_skip_bits_write(skip_bits: &SkipBits, description: &str) -> TokenStream {
+    let crate_name = get_crate_name();
+    let count = skip_bits.bits;
+    quote! {
+        ::#crate_name::reserved::write_reserved_bits::<_, T>(buf, #count)
+            .with_context(|| format!("Writing {}", #description))?;
+    }
+}
+
+/// Get each 'length_of' field of a struct along with the field it refers to
+pub(crate) fn length_references(
+    fields: &[ParselyWriteFieldData],
+) -> Vec<(syn::Member, Vec<syn::Member>)> {
+    fields
+        .iter()
+        .filter_map(|f| {
+            let length_of = f.length_of.as_ref()?;
+            Some((f.common.ident.clone(), vec![length_of.field.clone()]))
+        })
+        .collect()
+}
+
+/// Wrap `inner` (which writes the struct fields `fields`) so that it writes to a separate buffer,
+/// in which the fields which are only known once the struct has been written are then filled in
+/// before the result is written to the buffer: first each 'length_of' field with the length of the
+/// field it refers to, then each checksum field with the checksum of the bits in its range.
+/// `message_start` is the statement which records the start of the message, if any is needed.
+pub(crate) fn wrap_write_with_backpatching(
+    fields: &[ParselyWriteFieldData],
+    message_start: Option<TokenStream>,
+    inner: TokenStream,
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    let struct_end = struct_end_position_ident();
+    let length_writes = fields.iter().filter_map(|f| {
+        let length_of = f.length_of.as_ref()?;
+        let field_type = &f.common.ty;
+        let (field_start, field_end) = field_position_idents(&f.common.ident);
+        let (measured_start, measured_end) = field_position_idents(&length_of.field);
+        let (units, adjust) = (length_of.units, length_of.adjust);
+        let field_name_string = f.common.ident.as_friendly_string();
+        Some(quote! {
+            ::#crate_name::parsely_write::backpatch_length::<#field_type, T>(
+                buf,
+                #measured_start..#measured_end,
+                #units,
+                #adjust,
+                #field_start..#field_end,
+            )
+            .with_context(|| format!("Writing length field '{}'", #field_name_string))?;
+        })
+    });
+    let checksum_writes = fields.iter().filter_map(|f| {
+        let checksum = f.common.checksum.as_ref()?;
+        let algorithm = &checksum.algorithm;
+        let field_type = &f.common.ty;
+        let range = checksum_range_tokens(&checksum.range, &f.common.ident);
+        let (field_start, field_end) = field_position_idents(&f.common.ident);
+        let field_name_string = f.common.ident.as_friendly_string();
+        Some(quote! {
+            ::#crate_name::checksum::write_checksum::<#algorithm, #field_type, T>(
+                buf,
+                #range,
+                #field_start..#field_end,
+            )
+            .with_context(|| format!("Writing checksum field '{}'", #field_name_string))?;
+        })
+    });
+    quote! {
+        let mut backpatch_buf = ::#crate_name::BitsMut::new();
+        {
+            let buf = &mut backpatch_buf;
+            #message_start
+
+            #inner
+
+            #[allow(unused_variables)]
+            let #struct_end = buf.len_bits();
+            #(#length_writes)*
+            #(#checksum_writes)*
+        }
+        buf.try_put_bit_slice(&backpatch_buf).context("Writing backpatched data")?;
+    }
+}
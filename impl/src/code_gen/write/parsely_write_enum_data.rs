@@ -1,13 +1,21 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+use darling::ast;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
 use crate::{
-    get_crate_name, model_types::TypedFnArgList, syn_helpers::MemberExts, ParselyWriteReceiver,
+    code_gen::{
+        helpers::{check_message_alignment, generate_message_start},
+        parsely_common_variant_data::ParselyCommonVariantData,
+    },
+    get_crate_name,
+    model_types::{Alignment, Hooks, TypedFnArgList, Validation, VariantId, VariantIdDecl},
+    syn_helpers::MemberExts,
+    ParselyWriteReceiver,
 };
 
 use super::{
-    helpers::{wrap_write_with_padding_handling, ParentType},
+    helpers::{generate_magic_write, wrap_write_with_padding_handling, ParentType},
     parsely_write_field_data::ParselyWriteFieldData,
     parsely_write_variant_data::ParselyWriteVariantData,
 };
@@ -15,7 +23,10 @@ use super::{
 pub(crate) struct ParselyWriteEnumData {
     pub(crate) ident: syn::Ident,
     pub(crate) required_context: TypedFnArgList,
-    pub(crate) alignment: Option<usize>,
+    pub(crate) alignment: Option<Alignment>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) validate: Option<Validation>,
+    pub(crate) hooks: Hooks,
     pub(crate) sync_args: TypedFnArgList,
     pub(crate) variants: Vec<ParselyWriteVariantData>,
 }
@@ -24,15 +35,46 @@ impl TryFrom<ParselyWriteReceiver> for ParselyWriteEnumData {
     type Error = anyhow::Error;
 
     fn try_from(value: ParselyWriteReceiver) -> Result<Self, Self::Error> {
-        let key_type = value
-            .key_type
-            .ok_or(anyhow!("'key_type' attribute is required on enums"))?;
-        let variants = value
-            .data
-            .take_enum()
-            .ok_or(anyhow!("Not an enum"))?
+        if value.key_type.is_none() && value.key.is_none() && !value.peek_key {
+            bail!(
+                "'key_type' attribute is required on enums (if the tag isn't written separately, \
+                say so with 'key' or 'peek_key')"
+            );
+        }
+        if let Some(syn::Type::Tuple(_)) = value.key_type {
+            if value.key_context.is_some() {
+                bail!("'key_context' can't be used with a tuple 'key_type'");
+            }
+        }
+        let key_context_values = value
+            .key_context
+            .map(|c| c.expressions(&format!("Key context for enum '{}'", value.ident)))
+            .unwrap_or_default();
+        let variants = value.data.take_enum().ok_or(anyhow!("Not an enum"))?;
+        // Variants have implicit discriminants if the enum is fieldless or has a #[repr]
+        let implicit_discriminants = value.attrs.iter().any(|a| a.path().is_ident("repr"))
+            || variants.iter().all(|v| v.fields.is_empty());
+        let ids = VariantId::assign(
+            &value.ident,
+            variants.iter().map(|v| VariantIdDecl {
+                ident: &v.ident,
+                id: v.common.id.clone(),
+                discriminant: v.discriminant.clone(),
+                holds_key: v.common.unknown && !v.fields.is_empty(),
+            }),
+            implicit_discriminants,
+        )?;
+        let variants = variants
             .into_iter()
-            .map(|v| {
+            .zip(ids)
+            .map(|(v, id)| {
+                if v.common.unknown && (v.fields.len() > 1 || v.fields.style == ast::Style::Struct) {
+                    bail!(
+                        "Unknown variant '{}' can only have a single unnamed field (to hold the key)",
+                        v.ident
+                    );
+                }
+                let unknown = v.common.unknown;
                 let data_fields = v
                     .fields
                     .into_iter()
@@ -45,20 +87,73 @@ impl TryFrom<ParselyWriteReceiver> for ParselyWriteEnumData {
                         ParselyWriteFieldData::from_receiver(ident, ParentType::Enum, field)
                     })
                     .collect::<Vec<_>>();
-                ParselyWriteVariantData {
-                    enum_name: value.ident.clone(),
-                    ident: v.ident,
-                    discriminant: v.discriminant,
-                    id: v.id,
-                    key_type: key_type.clone(),
-                    fields: data_fields,
+                // With 'peek_key', variants write their tag themselves as part of their fields.
+                // An unknown variant doesn't read it again, and neither does a variant without any
+                // fields, so those write it separately.
+                let writes_tag = !value.peek_key
+                    || unknown
+                    || data_fields.iter().all(|f| f.common.skip.is_some());
+                if value.peek_key && writes_tag && id.as_ref().is_some_and(VariantId::is_pattern) {
+                    bail!(
+                        "Variant '{}' of enum '{}' doesn't write its tag as part of its fields, so \
+                        with 'peek_key' its id needs to be a value rather than a pattern",
+                        v.ident,
+                        value.ident
+                    );
                 }
+                Ok(ParselyWriteVariantData {
+                    common: ParselyCommonVariantData::from_receiver(
+                        value.ident.clone(),
+                        v.ident,
+                        v.common,
+                    )?,
+                    id,
+                    key_type: if writes_tag {
+                        value.key_type.clone()
+                    } else {
+                        None
+                    },
+                    key_context_values: key_context_values.clone(),
+                    fields: data_fields,
+                })
             })
-            .collect::<Vec<_>>();
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if variants.iter().filter(|v| v.common.unknown).count() > 1 {
+            bail!("Only one variant can be marked 'unknown'");
+        }
+        check_message_alignment(&value.ident, value.alignment.as_ref())?;
+        if variants
+            .iter()
+            .flat_map(|v| v.fields.iter())
+            .any(|f| f.common.trailing_padding.is_some())
+        {
+            bail!("'trailing_padding' can only be used on struct fields");
+        }
+        if variants
+            .iter()
+            .flat_map(|v| v.fields.iter())
+            .any(|f| f.common.checksum.is_some() || f.length_of.is_some())
+        {
+            bail!("'checksum' and 'length_of' can only be used on struct fields");
+        }
+        if value.pad_to_bytes.is_some() {
+            bail!("'pad_to_bytes' can't be used on enums: use 'length_bytes' on their variants");
+        }
+        if !value.trailing_const_field.is_empty() {
+            bail!("'trailing_const_field' can only be used on structs");
+        }
         Ok(ParselyWriteEnumData {
             ident: value.ident,
             required_context: value.required_context,
             alignment: value.alignment,
+            magic: value.magic,
+            validate: value.validate,
+            hooks: Hooks {
+                pre_read: value.pre_read,
+                post_read: value.post_read,
+                pre_write: value.pre_write,
+                post_write: value.post_write,
+            },
             sync_args: value.sync_args,
             variants,
         })
@@ -73,14 +168,16 @@ impl ToTokens for ParselyWriteEnumData {
             (self.required_context.names(), self.required_context.types());
         let match_arms = &self.variants;
 
+        let magic_write = self.magic.as_ref().map(generate_magic_write);
         let body = quote! {
+            #magic_write
             match self {
                 #(#match_arms)*
                 _ => ParselyResult::<()>::Err(anyhow!("No arms matched self"))?,
             }
         };
 
-        let body = if let Some(alignment) = self.alignment {
+        let body = if let Some(ref alignment) = self.alignment {
             wrap_write_with_padding_handling(
                 &syn::Member::Named(self.ident.clone()),
                 alignment,
@@ -90,16 +187,38 @@ impl ToTokens for ParselyWriteEnumData {
             body
         };
 
+        let message_start = generate_message_start(
+            self.variants.iter().flat_map(|v| {
+                v.common
+                    .alignment
+                    .iter()
+                    .chain(v.fields.iter().filter_map(|f| f.common.alignment.as_ref()))
+            }),
+            quote! { buf.remaining_mut_bits() },
+        );
+
         let (sync_args_variables, sync_args_types) =
             (self.sync_args.names(), self.sync_args.types());
 
+        let validation = self
+            .validate
+            .as_ref()
+            .map(|validate| validate.to_check_tokens(enum_name, quote! { self }));
+        let enum_description = format!("'{enum_name}'");
+        let pre_write = self.hooks.pre_write_tokens(&enum_description);
+        let post_write = self.hooks.post_write_tokens(&enum_description);
+
         // TODO: need to think about what the sync impl for an enum should look like and finish
         // that
         tokens.extend(quote! {
             impl<B: BitBufMut> ::#crate_name::ParselyWrite<B> for #enum_name {
                 type Ctx = (#(#context_types,)*);
                 fn write<T: ByteOrder>(&self, buf: &mut B, (#(#context_variables,)*): Self::Ctx,) -> ParselyResult<()> {
+                    #validation
+                    #pre_write
+                    #message_start
                     #body
+                    #post_write
 
                     Ok(())
                 }
@@ -2,13 +2,19 @@ use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
 use crate::{
-    code_gen::parsely_common_field_data::ParselyCommonFieldData,
-    model_types::{Context, ExprOrFunc},
+    code_gen::{
+        helpers::generate_position_markers, parsely_common_field_data::ParselyCommonFieldData,
+    },
+    get_crate_name,
+    model_types::{Context, ExprOrFunc, LengthOf},
     syn_helpers::{MemberExts, TypeExts},
     ParselyWriteFieldReceiver,
 };
 
-use super::helpers::{wrap_write_with_padding_handling, ParentType};
+use super::helpers::{
+    generate_skip_bits_write, padding_flag_position_ident, wrap_write_with_padding_handling,
+    wrap_write_with_size_padding, ParentType,
+};
 
 #[derive(Debug)]
 pub(crate) struct ParselyWriteFieldData {
@@ -23,6 +29,11 @@ pub(crate) struct ParselyWriteFieldData {
     /// sync method provides an opportunity to synchronize "linked" fields, where one field's value
     /// depends on the value of another.
     pub(crate) sync_with: Context,
+    /// A function which writes the value instead of its type's `ParselyWrite` implementation
+    pub(crate) writer: Option<syn::Path>,
+    /// Set if this field is filled in with the length of another field once its struct has been
+    /// written
+    pub(crate) length_of: Option<LengthOf>,
 }
 
 impl ParselyWriteFieldData {
@@ -31,19 +42,54 @@ impl ParselyWriteFieldData {
         parent_type: ParentType,
         receiver: ParselyWriteFieldReceiver,
     ) -> Self {
-        let common = ParselyCommonFieldData {
-            ident: field_ident,
-            ty: receiver.ty,
-            assertion: receiver.common.assertion,
-            context: receiver.common.context,
-            map: receiver.common.map,
-            alignment: receiver.common.alignment,
-        };
+        let field_name = field_ident.as_friendly_string();
+        if receiver.length_of.is_none() && (receiver.units.is_some() || receiver.adjust.is_some()) {
+            panic!("Field '{field_name}': 'units' and 'adjust' can only be used with 'length_of'");
+        }
+        if receiver.common.skip
+            && (receiver.writer.is_some()
+                || receiver.sync_expr.is_some()
+                || !receiver.sync_with.is_empty()
+                || receiver.length_of.is_some())
+        {
+            panic!("Field '{field_name}': 'skip' can't be combined with attributes for writing the field");
+        }
+        if receiver.writer.is_some()
+            && (receiver.common.as_type.is_some()
+                || receiver.common.steps.field.has_map()
+                || receiver.common.steps.elem.has_map())
+        {
+            panic!("Field '{field_name}': 'writer' can't be combined with 'as' or 'map'");
+        }
+        if receiver.units == Some(0) {
+            panic!("Field '{field_name}': 'units' must be non-zero");
+        }
+        if receiver.length_of.is_some()
+            && (receiver.ty.is_wrapped()
+                || receiver.common.as_type.is_some()
+                || receiver.common.steps.field.has_map()
+                || receiver.common.alignment.is_some()
+                || receiver.common.pad_to_bytes.is_some()
+                || receiver.common.checksum.is_some())
+        {
+            panic!(
+                "Field '{field_name}': a 'length_of' field can't be an Option or collection or have 'as', 'map', 'alignment', 'pad_to_bytes' or 'checksum'"
+            );
+        }
+        let length_of = receiver.length_of.map(|field| LengthOf {
+            field: field.0,
+            units: receiver.units.unwrap_or(1),
+            adjust: receiver.adjust.unwrap_or(0),
+        });
+        let common =
+            ParselyCommonFieldData::from_receiver(field_ident, receiver.ty, receiver.common);
         Self {
             common,
             parent_type,
             sync_expr: receiver.sync_expr,
             sync_with: receiver.sync_with,
+            writer: receiver.writer,
+            length_of,
         }
     }
 
@@ -58,14 +104,22 @@ impl ParselyWriteFieldData {
     pub(crate) fn to_sync_call_tokens(&self) -> TokenStream {
         let field_ident = &self.common.ident;
         let field_name_string = field_ident.as_friendly_string();
-        if let Some(ref sync_expr) = self.sync_expr {
+        if self.common.skip.is_some() {
+            // Skipped fields aren't written, so they don't need syncing
+            quote! {}
+        } else if let Some(ref sync_expr) = self.sync_expr {
             quote! {
                 self.#field_ident = (#sync_expr).into_parsely_result().with_context(|| format!("Syncing field '{}'", #field_name_string))?;
             }
-        } else if self.sync_with.is_empty() && self.common.ty.is_wrapped() {
+        } else if self.sync_with.is_empty()
+            && (self.common.ty.is_wrapped()
+                || self.common.as_type.is_some()
+                || self.writer.is_some())
+        {
             // We'll allow this combination to skip a call to sync: for types like Option<T> or
             // Vec<T>, synchronization is only going to make sense if a custom function was
-            // provided.
+            // provided.  The same goes for fields which are converted to another type or written
+            // by a custom function, as the field's type doesn't need to be writable.
             quote! {}
         } else {
             let sync_with = self.sync_with_expressions();
@@ -76,13 +130,47 @@ impl ParselyWriteFieldData {
     }
 }
 
+impl ParselyWriteFieldData {
+    /// Get the expression which writes `value` (a reference to a value of this field's buffer
+    /// type).  If an 'as' type was given, the value is converted to it via `TryFrom` first, and if a
+    /// 'writer' function was given it writes the value.
+    fn to_write_value_tokens(&self, value: TokenStream) -> TokenStream {
+        let write_type = self.common.buffer_type();
+        // Context values that we need to pass to this field's ParselyWrite::write method
+        let context_values = self.common.context_values();
+        if let Some(ref writer) = self.writer {
+            quote! {
+                #writer::<_, T>(#value, buf, (#(#context_values,)*))
+            }
+        } else if let Some(ref as_type) = self.common.as_type {
+            let as_type_string = quote! { #as_type }.to_string();
+            quote! {
+                <#as_type as TryFrom<#write_type>>::try_from(<#write_type as Clone>::clone(#value))
+                    .map_err(|e| anyhow!(e))
+                    .with_context(|| format!("Converting to {}", #as_type_string))
+                    .and_then(|v| <#as_type>::write::<T>(&v, buf, (#(#context_values,)*)))
+            }
+        } else {
+            quote! {
+                <#write_type>::write::<T>(#value, buf, (#(#context_values,)*))
+            }
+        }
+    }
+}
+
 impl ToTokens for ParselyWriteFieldData {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let field_ident = &self.common.ident;
         let field_name_string = field_ident.as_friendly_string();
-        let write_type = self.common.buffer_type();
-        // Context values that we need to pass to this field's ParselyWrite::write method
-        let context_values = self.common.context_values();
+        if self.common.skip.is_some() {
+            // The field isn't in the buffer, but its (empty) position may still be needed
+            let (checksum_start, checksum_end) = generate_position_markers(&self.common);
+            tokens.extend(quote! {
+                #checksum_start
+                #checksum_end
+            });
+            return;
+        }
 
         let mut output = TokenStream::new();
         let field_var = if matches!(self.parent_type, ParentType::Struct) {
@@ -92,36 +180,103 @@ impl ToTokens for ParselyWriteFieldData {
             quote! { #field_name }
         };
 
-        if let Some(ref assertion) = self.common.assertion {
-            assertion.to_write_assertion_tokens(&self.common.ident, &mut output);
-        }
+        // Apply any assertions and maps in the order they were declared
+        let value =
+            self.common
+                .steps
+                .to_write_tokens(field_ident, quote! { &#field_var }, &mut output);
 
-        if let Some(ref map_expr) = self.common.map {
-            map_expr.to_write_map_tokens(&self.common.ident, &mut output);
+        if self.common.steps.has_map() {
+            let crate_name = get_crate_name();
+            let context_values = self.common.context_values();
+            output = quote! {
+                {
+                    #output
+                    ::#crate_name::ParselyWrite::write::<T>(#value, buf, (#(#context_values,)*))
+                        .with_context(|| format!("Writing mapped value for field '{}'", #field_name_string))?;
+                }
+            };
         } else if self.common.ty.is_option() {
+            let write_value = self.to_write_value_tokens(quote! { v });
             output.extend(quote! {
                     if let Some(ref v) = #field_var {
-                        #write_type::write::<T>(v, buf, (#(#context_values,)*)).with_context(|| format!("Writing field '{}'", #field_name_string))?;
+                        #write_value.with_context(|| format!("Writing field '{}'", #field_name_string))?;
                     }
                 });
+        } else if self.common.ty.is_collection() && self.common.has_elem_attributes() {
+            let mut element_write = TokenStream::new();
+            let value = self.common.elem_steps.to_write_tokens(
+                field_ident,
+                quote! { v },
+                &mut element_write,
+            );
+            let write_value = if self.common.elem_steps.has_map() {
+                let crate_name = get_crate_name();
+                let context_values = self.common.context_values();
+                quote! { ::#crate_name::ParselyWrite::write::<T>(#value, buf, (#(#context_values,)*)) }
+            } else {
+                self.to_write_value_tokens(value)
+            };
+            output.extend(quote! {
+                    #field_var.iter().enumerate().map(|(idx, v)| {
+                        // The elements before this one
+                        #[allow(unused_variables)]
+                        let items = &#field_var[..idx];
+                        (|| {
+                            #element_write
+                            #write_value
+                        })().with_context(|| format!("Index {idx}"))
+                    }).collect::<ParselyResult<Vec<_>>>().with_context(|| format!("Writing field '{}'", #field_name_string))?;
+                });
         } else if self.common.ty.is_collection() {
+            let write_value = self.to_write_value_tokens(quote! { v });
             output.extend(quote! {
                     #field_var.iter().enumerate().map(|(idx, v)| {
-                        #write_type::write::<T>(v, buf, (#(#context_values,)*)).with_context(|| format!("Index {idx}"))
+                        #write_value.with_context(|| format!("Index {idx}"))
                     }).collect::<ParselyResult<Vec<_>>>().with_context(|| format!("Writing field '{}'", #field_name_string))?;
                 });
         } else {
+            let write_value = self.to_write_value_tokens(quote! { &#field_var });
             output.extend(quote! {
-                    #write_type::write::<T>(&#field_var, buf, (#(#context_values,)*)).with_context(|| format!("Writing field '{}'", #field_name_string))?;
-                });
+                #write_value.with_context(|| format!("Writing field '{}'", #field_name_string))?;
+            });
         }
 
-        output = if let Some(alignment) = self.common.alignment {
+        if let Some(ref size_bytes) = self.common.pad_to_bytes {
+            output = wrap_write_with_size_padding(field_ident, size_bytes, output);
+        }
+        output = if let Some(ref alignment) = self.common.alignment {
             wrap_write_with_padding_handling(field_ident, alignment, output)
         } else {
             output
         };
 
-        tokens.extend(output);
+        let field_description = format!("field '{field_name_string}'");
+        tokens.extend(self.common.hooks.pre_write_tokens(&field_description));
+        if let Some(ref skip_bits) = self.common.skip_bits {
+            tokens.extend(generate_skip_bits_write(
+                skip_bits,
+                &format!("reserved bits before field '{field_name_string}'"),
+            ));
+        }
+        tokens.extend(
+            self.common
+                .const_fields
+                .to_write_tokens(&format!("constant before field '{field_name_string}'")),
+        );
+        if self.common.trailing_padding.is_some() {
+            // Remember where the flag is, so it can be set once the amount of padding is known
+            let flag_position = padding_flag_position_ident();
+            tokens.extend(quote! {
+                let #flag_position = buf.len_bits();
+            });
+        }
+        let (checksum_start, checksum_end) = generate_position_markers(&self.common);
+        tokens.extend(quote! {
+            #checksum_start
+            #output
+            #checksum_end
+        });
+        tokens.extend(self.common.hooks.post_write_tokens(&field_description));
     }
 }
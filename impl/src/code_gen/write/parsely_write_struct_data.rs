@@ -1,21 +1,40 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
 use crate::{
-    get_crate_name, model_types::TypedFnArgList, syn_helpers::MemberExts, ParselyWriteReceiver,
+    get_crate_name,
+    model_types::{Alignment, ConstFields, Hooks, TypedFnArgList, Validation},
+    syn_helpers::MemberExts,
+    ParselyWriteReceiver,
+};
+
+use crate::code_gen::helpers::{
+    check_message_alignment, checksum_references, generate_message_start, mark_field_positions,
 };
 
 use super::{
-    helpers::{wrap_write_with_padding_handling, ParentType},
+    helpers::{
+        generate_magic_write, length_references, wrap_write_with_backpatching,
+        wrap_write_with_padding_handling, wrap_write_with_size_padding,
+        wrap_write_with_trailing_padding, ParentType,
+    },
     parsely_write_field_data::ParselyWriteFieldData,
 };
 
 pub(crate) struct ParselyWriteStructData {
     pub(crate) ident: syn::Ident,
     pub(crate) required_context: TypedFnArgList,
-    pub(crate) alignment: Option<usize>,
+    pub(crate) alignment: Option<Alignment>,
+    pub(crate) pad_to_bytes: Option<syn::Expr>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) trailing_const_fields: ConstFields,
+    pub(crate) validate: Option<Validation>,
+    pub(crate) hooks: Hooks,
     pub(crate) sync_args: TypedFnArgList,
+    /// Whether any of the fields is filled in once the struct has been written (a checksum or
+    /// length)
+    pub(crate) has_backpatching: bool,
     pub(crate) fields: Vec<ParselyWriteFieldData>,
 }
 
@@ -24,7 +43,7 @@ impl TryFrom<ParselyWriteReceiver> for ParselyWriteStructData {
 
     fn try_from(value: ParselyWriteReceiver) -> Result<Self, Self::Error> {
         let struct_receiver_fields = value.data.take_struct().ok_or(anyhow!("Not a struct"))?;
-        let data_fields = struct_receiver_fields
+        let mut data_fields = struct_receiver_fields
             .into_iter()
             .enumerate()
             .map(|(field_index, field)| {
@@ -33,12 +52,41 @@ impl TryFrom<ParselyWriteReceiver> for ParselyWriteStructData {
                 ParselyWriteFieldData::from_receiver(ident, ParentType::Struct, field)
             })
             .collect::<Vec<_>>();
+        check_message_alignment(&value.ident, value.alignment.as_ref())?;
+        let trailing_padding = data_fields
+            .iter()
+            .filter_map(|f| f.common.trailing_padding.as_ref())
+            .collect::<Vec<_>>();
+        if trailing_padding.len() > 1 {
+            bail!("Only one field can be marked 'trailing_padding'");
+        }
+        if trailing_padding.iter().any(|t| t.alignment.is_none()) {
+            bail!("'trailing_padding' needs an alignment to pad to when writing");
+        }
+        let mut references = checksum_references(data_fields.iter().map(|f| &f.common));
+        references.extend(length_references(&data_fields));
+        let has_backpatching = mark_field_positions(
+            &value.ident,
+            data_fields.iter_mut().map(|f| &mut f.common),
+            &references,
+        )?;
 
         Ok(ParselyWriteStructData {
             ident: value.ident,
             required_context: value.required_context,
             alignment: value.alignment,
+            pad_to_bytes: value.pad_to_bytes,
+            magic: value.magic,
+            trailing_const_fields: value.trailing_const_field,
+            validate: value.validate,
+            hooks: Hooks {
+                pre_read: value.pre_read,
+                post_read: value.post_read,
+                pre_write: value.pre_write,
+                post_write: value.post_write,
+            },
             sync_args: value.sync_args,
+            has_backpatching,
             fields: data_fields,
         })
     }
@@ -52,8 +100,36 @@ impl ToTokens for ParselyWriteStructData {
             (self.required_context.names(), self.required_context.types());
 
         let fields = &self.fields;
+        let mut message_start = generate_message_start(
+            fields.iter().filter_map(|f| f.common.alignment.as_ref()),
+            quote! { buf.remaining_mut_bits() },
+        );
+        let magic_write = self.magic.as_ref().map(generate_magic_write);
+        let trailing_const_writes = self
+            .trailing_const_fields
+            .to_write_tokens(&format!("constant after the fields of '{struct_name}'"));
         let field_writes = quote! {
+            #magic_write
             #(#fields)*
+            #trailing_const_writes
+        };
+        // The fields are written to a separate buffer when there's trailing padding or backpatching,
+        // so the message start needs to be recorded in that buffer
+        let field_writes = match fields
+            .iter()
+            .find_map(|f| f.common.trailing_padding.as_ref())
+        {
+            Some(trailing_padding) => wrap_write_with_trailing_padding(
+                trailing_padding
+                    .alignment
+                    .expect("Trailing padding alignment was checked"),
+                message_start.take(),
+                field_writes,
+            ),
+            None if self.has_backpatching => {
+                wrap_write_with_backpatching(fields, message_start.take(), field_writes)
+            }
+            None => field_writes,
         };
 
         let sync_field_calls = fields
@@ -64,7 +140,17 @@ impl ToTokens for ParselyWriteStructData {
         let (sync_args_variables, sync_args_types) =
             (self.sync_args.names(), self.sync_args.types());
 
-        let body = if let Some(alignment) = self.alignment {
+        let field_writes = if let Some(ref size_bytes) = self.pad_to_bytes {
+            wrap_write_with_size_padding(
+                &syn::Member::Named(self.ident.clone()),
+                size_bytes,
+                field_writes,
+            )
+        } else {
+            field_writes
+        };
+
+        let body = if let Some(ref alignment) = self.alignment {
             wrap_write_with_padding_handling(
                 &syn::Member::Named(self.ident.clone()),
                 alignment,
@@ -74,6 +160,14 @@ impl ToTokens for ParselyWriteStructData {
             field_writes
         };
 
+        let validation = self
+            .validate
+            .as_ref()
+            .map(|validate| validate.to_check_tokens(struct_name, quote! { self }));
+        let struct_description = format!("'{struct_name}'");
+        let pre_write = self.hooks.pre_write_tokens(&struct_description);
+        let post_write = self.hooks.post_write_tokens(&struct_description);
+
         tokens.extend(quote! {
             impl<B: BitBufMut> ::#crate_name::ParselyWrite<B> for #struct_name {
                 type Ctx = (#(#context_types,)*);
@@ -82,9 +176,14 @@ impl ToTokens for ParselyWriteStructData {
                     buf: &mut B,
                     (#(#context_variables,)*): Self::Ctx,
                 ) -> ParselyResult<()> {
+                    #validation
+                    #pre_write
+                    #message_start
 
                     #body
 
+                    #post_write
+
                     Ok(())
                 }
             }
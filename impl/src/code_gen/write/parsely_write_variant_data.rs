@@ -1,15 +1,25 @@
-use crate::{get_crate_name, syn_helpers::MemberExts};
+use crate::{
+    code_gen::parsely_common_variant_data::ParselyCommonVariantData, get_crate_name,
+    model_types::VariantId, syn_helpers::MemberExts,
+};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
-use super::parsely_write_field_data::ParselyWriteFieldData;
+use super::{
+    helpers::wrap_write_with_padding_handling, parsely_write_field_data::ParselyWriteFieldData,
+};
 
 pub(crate) struct ParselyWriteVariantData {
-    pub(crate) enum_name: syn::Ident,
-    pub(crate) ident: syn::Ident,
-    pub(crate) id: syn::Expr,
-    pub(crate) discriminant: Option<syn::Expr>,
-    pub(crate) key_type: syn::Type,
+    pub(crate) common: ParselyCommonVariantData,
+    /// This variant's id, which only an unknown variant that holds the key doesn't have
+    pub(crate) id: Option<VariantId>,
+    /// The type of the tag to write for this variant.  If this isn't set, no tag is written
+    /// (because the tag isn't held in the buffer).
+    pub(crate) key_type: Option<syn::Type>,
+    /// Context values that need to be passed when writing the tag
+    pub(crate) key_context_values: Vec<syn::Expr>,
+    /// For an unknown variant, this is the field (if any) that holds the key, which is written as
+    /// the tag.
     pub(crate) fields: Vec<ParselyWriteFieldData>,
 }
 
@@ -22,57 +32,142 @@ impl ParselyWriteVariantData {
     }
 }
 
+/// Generate a write of `value` (a reference to an enum's key of type `key_type`).  A tuple key is
+/// written one element at a time, so each element only needs to implement `ParselyWrite` itself.
+fn generate_key_write(
+    key_type: &syn::Type,
+    value: TokenStream,
+    key_context_values: &[syn::Expr],
+) -> TokenStream {
+    let crate_name = get_crate_name();
+    if let syn::Type::Tuple(ref tuple) = key_type {
+        let element_writes = (0..tuple.elems.len()).map(|idx| {
+            let idx = syn::Index::from(idx);
+            quote! {
+                ::#crate_name::ParselyWrite::write::<T>(&(#value).#idx, buf, ())?;
+            }
+        });
+        quote! {
+            #(#element_writes)*
+        }
+    } else {
+        quote! {
+            ::#crate_name::ParselyWrite::write::<T>(#value, buf, (#(#key_context_values,)*))?;
+        }
+    }
+}
+
 impl ToTokens for ParselyWriteVariantData {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let crate_name = get_crate_name();
-        let enum_name = &self.enum_name;
-        let variant_name = &self.ident;
+        let enum_name = &self.common.enum_name;
+        let variant_name = &self.common.ident;
+        let variant_name_string = self.common.friendly_name();
 
-        let tag_expr = &self.id;
-        let tag_type = &self.key_type;
-        let tag_write = quote! {
-            let tag_value: #tag_type = #tag_expr;
-            ::#crate_name::ParselyWrite::write::<T>(&tag_value, buf, ())?;
-        };
+        let key_context_values = &self.key_context_values;
+        if self.common.unknown && !self.fields.is_empty() {
+            // An unknown variant's field holds its key, so it's written as the tag
+            let field_name = self.fields[0].common.ident.as_variable_name();
+            if let Some(ref tag_type) = self.key_type {
+                let key_write =
+                    generate_key_write(tag_type, quote! { #field_name }, key_context_values);
+                tokens.extend(quote! {
+                    #enum_name::#variant_name(ref #field_name) => {
+                        #key_write
+                    }
+                });
+            } else {
+                tokens.extend(quote! {
+                    #enum_name::#variant_name(..) => {}
+                });
+            }
+            return;
+        }
 
-        let body = if let Some(ref discriminant) = self.discriminant {
+        let tag_expr = self
+            .id
+            .as_ref()
+            .expect("Only an unknown variant that holds the key can be without an id")
+            .value();
+        let tag_write = if let Some(ref tag_type) = self.key_type {
+            let key_write = generate_key_write(tag_type, quote! { &tag_value }, key_context_values);
             quote! {
-                #enum_name::#variant_name => {
-                    #tag_write
-                    let discriminant_value = #discriminant;
+                let tag_value: #tag_type = #tag_expr;
+                #key_write
+            }
+        } else {
+            quote! {}
+        };
+
+        let fields = &self.fields;
+        let mut field_writes = quote! {
+            #(#fields)*
+        };
+        if let Some(ref length) = self.common.length_bytes {
+            // Pad the fields out to the variant's length
+            field_writes = quote! {
+                let variant_length: usize = (#length).into_parsely_result().with_context(|| format!("Length of variant '{}'", #variant_name_string))?;
+                let bits_remaining_before_variant = buf.remaining_mut_bits();
 
-                    discriminant_value.write::<T>(buf, ()).context("Writing discriminant value of variant #variant_name")
+                #field_writes
+
+                let variant_bits_written = bits_remaining_before_variant - buf.remaining_mut_bits();
+                if variant_bits_written % 8 != 0 {
+                    bail!("Variant '{}' wrote {} bits, which isn't a whole number of bytes", #variant_name_string, variant_bits_written);
                 }
-            }
-        } else if !self.fields.is_empty() {
-            let fields = &self.fields;
-            let field_variable_names = fields
-                .iter()
-                .map(|f| f.common.ident.as_variable_name())
-                .collect::<Vec<_>>();
-            if self.named_fields() {
-                quote! {
-                    #enum_name::#variant_name { #(ref #field_variable_names,)* } => {
-                        #tag_write
-                        #(#fields)*
-                    }
+                let variant_bytes_written = variant_bits_written / 8;
+                if variant_bytes_written > variant_length {
+                    bail!("Variant '{}' wrote {} bytes, more than its length of {} bytes", #variant_name_string, variant_bytes_written, variant_length);
                 }
-            } else {
-                quote! {
-                    #enum_name::#variant_name(#(ref #field_variable_names,)*) => {
-                        #tag_write
-                        #(#fields)*
-                    }
+                for _ in variant_bytes_written..variant_length {
+                    buf.put_u8(0).context("Padding variant to its length")?;
                 }
-            }
-        } else {
-            quote! {
-                #enum_name::#variant_name => {
-                    #tag_write
+            };
+        }
+        if let Some(ref alignment) = self.common.alignment {
+            field_writes = wrap_write_with_padding_handling(
+                &syn::Member::Named(variant_name.clone()),
+                alignment,
+                field_writes,
+            );
+        }
+        // When no tag is written, the key is the variant's id (unless that's a pattern)
+        let key_binding = match self.key_type {
+            Some(_) => Some(quote! { &tag_value }),
+            None => self.id.as_ref().filter(|id| !id.is_pattern()).map(|id| {
+                let value = id.value();
+                quote! { &(#value) }
+            }),
+        };
+        let context_bindings = self.common.context_bindings(key_binding);
+        let assertion = self.common.assertion_tokens(quote! { self });
+
+        // Skipped fields aren't written, so they aren't bound
+        let field_bindings = fields
+            .iter()
+            .map(|f| match (f.common.skip.is_some(), &f.common.ident) {
+                (true, syn::Member::Named(ident)) => quote! { #ident: _ },
+                (true, syn::Member::Unnamed(_)) => quote! { _ },
+                (false, ident) => {
+                    let variable_name = ident.as_variable_name();
+                    quote! { ref #variable_name }
                 }
-            }
+            })
+            .collect::<Vec<_>>();
+        let pattern = if self.fields.is_empty() {
+            quote! { #enum_name::#variant_name }
+        } else if self.named_fields() {
+            quote! { #enum_name::#variant_name { #(#field_bindings,)* } }
+        } else {
+            quote! { #enum_name::#variant_name(#(#field_bindings,)*) }
         };
 
-        tokens.extend(body);
+        tokens.extend(quote! {
+            #pattern => {
+                #assertion
+                #tag_write
+                #context_bindings
+                #field_writes
+            }
+        });
     }
 }
@@ -53,3 +53,43 @@ where
         self.map_err(Into::into)
     }
 }
+
+/// Helper trait to coerce the result of a validation function, which can be either a `bool` or a
+/// `Result` (holding `()` or a `bool`), into a `ParselyResult<bool>`.
+pub trait IntoValidationResult {
+    fn into_validation_result(self) -> ParselyResult<bool>;
+}
+
+impl IntoValidationResult for bool {
+    fn into_validation_result(self) -> ParselyResult<bool> {
+        Ok(self)
+    }
+}
+
+impl<E> IntoValidationResult for Result<(), E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn into_validation_result(self) -> ParselyResult<bool> {
+        self.map(|_| true).map_err(Into::into)
+    }
+}
+
+impl<E> IntoValidationResult for Result<bool, E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn into_validation_result(self) -> ParselyResult<bool> {
+        self.map_err(Into::into)
+    }
+}
+
+/// Run the validation function `validation` against `value`.  Taking the function as an argument
+/// here means a closure's parameter type can be inferred.
+pub fn run_validation<V, R>(value: &V, validation: impl FnOnce(&V) -> R) -> ParselyResult<bool>
+where
+    V: ?Sized,
+    R: IntoValidationResult,
+{
+    validation(value).into_validation_result()
+}
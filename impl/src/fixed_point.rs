@@ -0,0 +1,215 @@
+use anyhow::{bail, Context};
+use bits_io::prelude::*;
+
+use crate::{
+    bounded_uint::{check_uint, checked_width, max_uint},
+    error::ParselyResult,
+    parsely_read::{read_uint, ParselyRead},
+    parsely_write::{write_uint, ParselyWrite, StateSync},
+};
+
+// Both fixed-point types hold their raw value in a 64 bit integer, which limits their total width
+// (INT + FRAC) to 64 bits.  Conversion to f64 is exact as long as the total width is at most 53
+// bits.
+
+/// An unsigned fixed-point number with `INT` integer bits and `FRAC` fractional bits (UQm.n),
+/// taking up `INT + FRAC` bits in the buffer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UFixed<const INT: u32, const FRAC: u32>(u64);
+
+/// A signed, two's complement fixed-point number with `INT` integer bits (including the sign bit)
+/// and `FRAC` fractional bits (Qm.n), taking up `INT + FRAC` bits in the buffer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IFixed<const INT: u32, const FRAC: u32>(i64);
+
+/// Scale `value` by 2^`FRAC`, failing if it isn't finite.  Scaling by a power of two is exact.
+fn scale(value: f64, frac: u32) -> ParselyResult<f64> {
+    if !value.is_finite() {
+        bail!("Can't convert {value} to a fixed-point value");
+    }
+    Ok(value * 2f64.powi(frac as i32))
+}
+
+fn check_exact(value: f64, scaled: f64) -> ParselyResult<f64> {
+    if scaled.fract() != 0.0 {
+        bail!("{value} can't be represented exactly as a fixed-point value");
+    }
+    Ok(scaled)
+}
+
+impl<const INT: u32, const FRAC: u32> UFixed<INT, FRAC> {
+    pub const BITS: u32 = checked_width(INT + FRAC);
+    pub const MAX_RAW: u64 = max_uint(Self::BITS);
+
+    /// Create a value from its raw bits.  Panics if `raw` doesn't fit in `INT + FRAC` bits.
+    pub const fn from_raw(raw: u64) -> Self {
+        assert!(raw <= Self::MAX_RAW, "Raw value out of range");
+        Self(raw)
+    }
+
+    pub fn try_from_raw(raw: u64) -> ParselyResult<Self> {
+        Ok(Self(check_uint(raw, Self::BITS)?))
+    }
+
+    pub const fn raw(&self) -> u64 {
+        self.0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64 / 2f64.powi(FRAC as i32)
+    }
+
+    /// Convert `value`, rounding to the nearest representable value.  Fails if `value` is out of
+    /// range.
+    pub fn from_f64_rounded(value: f64) -> ParselyResult<Self> {
+        Self::from_scaled(value, scale(value, FRAC)?.round())
+    }
+
+    /// Create a value from `value` after it has been scaled by 2^`FRAC` into an integral value.
+    /// The range is checked against 2^`BITS`, since `MAX_RAW` isn't exact as an `f64` when `BITS`
+    /// is over 53.
+    fn from_scaled(value: f64, scaled: f64) -> ParselyResult<Self> {
+        if scaled < 0.0 || scaled >= 2f64.powi(Self::BITS as i32) {
+            bail!("{value} is out of range for UFixed<{INT}, {FRAC}>");
+        }
+        Ok(Self(scaled as u64))
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> IFixed<INT, FRAC> {
+    pub const BITS: u32 = checked_width(INT + FRAC);
+    pub const MAX_RAW: i64 = i64::MAX >> (64 - Self::BITS);
+    pub const MIN_RAW: i64 = i64::MIN >> (64 - Self::BITS);
+
+    /// Create a value from its raw bits.  Panics if `raw` doesn't fit in `INT + FRAC` bits.
+    pub const fn from_raw(raw: i64) -> Self {
+        assert!(
+            raw >= Self::MIN_RAW && raw <= Self::MAX_RAW,
+            "Raw value out of range"
+        );
+        Self(raw)
+    }
+
+    pub fn try_from_raw(raw: i64) -> ParselyResult<Self> {
+        if raw < Self::MIN_RAW || raw > Self::MAX_RAW {
+            bail!("Raw value {raw} doesn't fit in {} bits", Self::BITS);
+        }
+        Ok(Self(raw))
+    }
+
+    pub const fn raw(&self) -> i64 {
+        self.0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64 / 2f64.powi(FRAC as i32)
+    }
+
+    /// Convert `value`, rounding to the nearest representable value.  Fails if `value` is out of
+    /// range.
+    pub fn from_f64_rounded(value: f64) -> ParselyResult<Self> {
+        Self::from_scaled(value, scale(value, FRAC)?.round())
+    }
+
+    /// Create a value from `value` after it has been scaled by 2^`FRAC` into an integral value.
+    /// The range is checked against ±2^(`BITS` - 1), since `MAX_RAW` isn't exact as an `f64` when
+    /// `BITS` is over 53.
+    fn from_scaled(value: f64, scaled: f64) -> ParselyResult<Self> {
+        let limit = 2f64.powi(Self::BITS as i32 - 1);
+        if scaled < -limit || scaled >= limit {
+            bail!("{value} is out of range for IFixed<{INT}, {FRAC}>");
+        }
+        Ok(Self(scaled as i64))
+    }
+}
+
+/// Converts `value` only if it can be represented exactly
+impl<const INT: u32, const FRAC: u32> TryFrom<f64> for UFixed<INT, FRAC> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::from_scaled(value, check_exact(value, scale(value, FRAC)?)?)
+    }
+}
+
+/// Converts `value` only if it can be represented exactly
+impl<const INT: u32, const FRAC: u32> TryFrom<f64> for IFixed<INT, FRAC> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::from_scaled(value, check_exact(value, scale(value, FRAC)?)?)
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> From<UFixed<INT, FRAC>> for f64 {
+    fn from(value: UFixed<INT, FRAC>) -> Self {
+        value.to_f64()
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> From<IFixed<INT, FRAC>> for f64 {
+    fn from(value: IFixed<INT, FRAC>) -> Self {
+        value.to_f64()
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> std::fmt::Display for UFixed<INT, FRAC> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_f64().fmt(f)
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> std::fmt::Display for IFixed<INT, FRAC> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_f64().fmt(f)
+    }
+}
+
+impl<B: BitBuf, const INT: u32, const FRAC: u32> ParselyRead<B> for UFixed<INT, FRAC> {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        let raw = read_uint::<B, T>(buf, Self::BITS)
+            .with_context(|| format!("Reading UFixed<{INT}, {FRAC}>"))?;
+        Ok(Self(raw))
+    }
+}
+
+impl<B: BitBufMut, const INT: u32, const FRAC: u32> ParselyWrite<B> for UFixed<INT, FRAC> {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_uint::<B, T>(buf, Self::BITS, self.0)
+            .with_context(|| format!("Writing UFixed<{INT}, {FRAC}>"))
+    }
+}
+
+impl<B: BitBuf, const INT: u32, const FRAC: u32> ParselyRead<B> for IFixed<INT, FRAC> {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        let raw = read_uint::<B, T>(buf, Self::BITS)
+            .with_context(|| format!("Reading IFixed<{INT}, {FRAC}>"))?;
+        // Sign-extend the raw bits
+        let shift = 64 - Self::BITS;
+        Ok(Self(((raw << shift) as i64) >> shift))
+    }
+}
+
+impl<B: BitBufMut, const INT: u32, const FRAC: u32> ParselyWrite<B> for IFixed<INT, FRAC> {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        let raw = (self.0 as u64) & max_uint(Self::BITS);
+        write_uint::<B, T>(buf, Self::BITS, raw)
+            .with_context(|| format!("Writing IFixed<{INT}, {FRAC}>"))
+    }
+}
+
+impl<const INT: u32, const FRAC: u32> StateSync for UFixed<INT, FRAC> {
+    type SyncCtx = ();
+}
+
+impl<const INT: u32, const FRAC: u32> StateSync for IFixed<INT, FRAC> {
+    type SyncCtx = ();
+}
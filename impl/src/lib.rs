@@ -1,9 +1,16 @@
+mod bounded_uint;
+pub mod checksum;
 mod code_gen;
 pub mod error;
+pub mod fixed_point;
 mod model_types;
+pub mod net;
 pub mod parsely_read;
 pub mod parsely_write;
+pub mod reserved;
+pub mod string;
 pub(crate) mod syn_helpers;
+pub mod time;
 
 pub use bits_io::{
     buf::bit_buf::BitBuf,
@@ -35,16 +42,20 @@ use code_gen::{
         parsely_write_struct_data::ParselyWriteStructData,
     },
 };
-use darling::{ast, FromDeriveInput, FromField, FromMeta, FromVariant};
-use model_types::{Assertion, Context, ExprOrFunc, MapExpr, TypedFnArgList};
+use darling::{ast, util::Override, FromDeriveInput, FromField, FromMeta, FromVariant};
+use model_types::{
+    Alignment, Assertion, ChecksumAlgorithm, ChecksumRange, ConstFields, Context, DeclaredSteps,
+    ExprOrFunc, FieldName, SkipBits, TrailingPadding, TypedFnArgList, Validation,
+};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::DeriveInput;
-use syn_helpers::TypeExts;
+use syn_helpers::{rename_keyword_attributes, TypeExts};
 
 #[doc(hidden)]
 pub fn derive_parsely_read(item: TokenStream) -> std::result::Result<TokenStream, syn::Error> {
-    let ast: DeriveInput = syn::parse2(item)?;
+    let mut ast: DeriveInput = syn::parse2(item)?;
+    rename_keyword_attributes(&mut ast)?;
     let data = ParselyReadReceiver::from_derive_input(&ast)?;
 
     // println!("{data:#?}");
@@ -64,7 +75,8 @@ pub fn derive_parsely_read(item: TokenStream) -> std::result::Result<TokenStream
 
 #[doc(hidden)]
 pub fn derive_parsely_write(item: TokenStream) -> std::result::Result<TokenStream, syn::Error> {
-    let ast: DeriveInput = syn::parse2(item)?;
+    let mut ast: DeriveInput = syn::parse2(item)?;
+    rename_keyword_attributes(&mut ast)?;
     let data = ParselyWriteReceiver::from_derive_input(&ast)?;
 
     if data.data.is_struct() {
@@ -86,17 +98,98 @@ pub struct ParselyCommonFieldReceiver {
     // See https://github.com/TedDriggs/darling/issues/330
 
     // generics: Option<syn::Ident>,
-    assertion: Option<Assertion>,
-
     /// Values that need to be passed as context to this fields read or write method
     context: Option<Context>,
 
-    /// An optional mapping that will be applied to the read value
-    map: Option<MapExpr>,
+    /// Values that need to be passed as context to the read or write method of each element of a
+    /// collection field.
+    elem_context: Option<Context>,
+
+    /// An optional indicator that this field is or needs to be aligned to the given alignment via
+    /// padding.
+    alignment: Option<Alignment>,
+
+    /// An optional total size in bytes that this field is padded to
+    pad_to_bytes: Option<syn::Expr>,
+
+    /// An optional type that this field's value is converted to and from (via `TryFrom`) when
+    /// writing it to or reading it from the buffer.  Writing converts a clone of the value, so
+    /// the field's type must implement `Clone`.
+    ///
+    /// This is given as `as` in the attribute, see [`syn_helpers::rename_keyword_attributes`].
+    as_type: Option<syn::Type>,
+
+    /// Constant values that come before this field in the buffer, but aren't stored in the Rust
+    /// type
+    #[darling(default)]
+    const_field: ConstFields,
+
+    /// Marks a boolean field as the flag for trailing padding at the end of the struct
+    trailing_padding: Option<TrailingPadding>,
+
+    /// A number of reserved bits that come before this field in the buffer.  They're skipped
+    /// (or checked to be zero) when reading and written as zeros.
+    skip_bits: Option<SkipBits>,
+
+    /// Expressions which are evaluated before and after this field is read or written.  They can
+    /// refer to `buf`, the context and the fields (including this one, after it's read).
+    pre_read: Option<syn::Expr>,
+    post_read: Option<syn::Expr>,
+    pre_write: Option<syn::Expr>,
+    post_write: Option<syn::Expr>,
+
+    /// Leave this field out of the buffer: it isn't read, written or synced.
+    #[darling(default)]
+    skip: bool,
+
+    /// The value that a skipped field is given when reading: either its type's default value
+    /// (`default`, which is also used when this isn't given) or the given expression
+    /// (`default = "expr"`), which can refer to the fields before it.
+    default: Option<Override<syn::Expr>>,
+
+    /// Marks this field as a checksum, computed with the given algorithm over the range of the
+    /// struct given by 'over'.  It's filled in when writing and verified when reading.
+    checksum: Option<ChecksumAlgorithm>,
+
+    /// The range of the struct that a checksum field covers.  Defaults to `start..this`.
+    over: Option<ChecksumRange>,
+
+    /// Any assertions and maps to apply to the value (or to each element), in the order they were
+    /// declared
+    #[darling(flatten)]
+    steps: DeclaredSteps,
+}
+
+#[derive(Debug, FromVariant, FromMeta)]
+pub struct ParselyCommonVariantReceiver {
+    // Note: as with ParselyCommonFieldReceiver, 'magic' fields (ident, discriminant, etc.) can't be
+    // held here.
+    /// The value of the enum's key which identifies this variant.  If not given, the variant's
+    /// discriminant is used.
+    id: Option<syn::Expr>,
 
-    /// An optional indicator that this field is or needs to be aligned to the given byte alignment
-    /// via padding.
-    alignment: Option<usize>,
+    /// This variant is used for any key that doesn't match another variant.  It can hold the key
+    /// in a single unnamed field.
+    #[darling(default)]
+    unknown: bool,
+
+    /// An optional indicator that this variant's fields need to be padded to the given alignment.
+    alignment: Option<Alignment>,
+
+    /// An assertion that is checked against the variant's value after reading it or before writing
+    /// it
+    assertion: Option<Assertion>,
+
+    /// Values that this variant's fields need, which are assigned from the expressions in
+    /// 'context'.  Those expressions can refer to the enum's required context and to the enum's
+    /// key (via `key`).
+    #[darling(default)]
+    required_context: TypedFnArgList,
+    context: Option<Context>,
+
+    /// The length, in bytes, of this variant's fields.  The fields can't read past this length and
+    /// any part of it they don't read is skipped.  When writing, the fields are padded to it.
+    length_bytes: Option<syn::Expr>,
 }
 
 #[derive(Debug, FromField)]
@@ -121,6 +214,10 @@ pub struct ParselyReadFieldReceiver {
 
     /// 'when' is required when there's an optional field
     when: Option<syn::Expr>,
+
+    /// A function which reads this field's value (or each element of a collection field) instead
+    /// of its type's `ParselyRead` implementation.  It's called as `reader::<_, T>(buf, context)`.
+    reader: Option<syn::Path>,
 }
 
 #[derive(Debug, FromVariant)]
@@ -128,7 +225,8 @@ pub struct ParselyReadFieldReceiver {
 pub struct ParselyReadVariantReceiver {
     ident: syn::Ident,
     discriminant: Option<syn::Expr>,
-    id: syn::Expr,
+    #[darling(flatten)]
+    common: ParselyCommonVariantReceiver,
     fields: ast::Fields<ParselyReadFieldReceiver>,
 }
 
@@ -151,6 +249,21 @@ pub struct ParselyWriteFieldReceiver {
     /// depends on the value of another.
     #[darling(default)]
     sync_with: Context,
+
+    /// A function which writes this field's value (or each element of a collection field) instead
+    /// of its type's `ParselyWrite` implementation.  It's called as
+    /// `writer::<_, T>(value, buf, context)`.
+    writer: Option<syn::Path>,
+
+    /// Fill this field in with the length of the given field, as it was actually written, once
+    /// the struct has been written.
+    length_of: Option<FieldName>,
+
+    /// The size in bytes of the units that a 'length_of' length is given in.  Defaults to 1.
+    units: Option<usize>,
+
+    /// A value that's added to a 'length_of' length.  Defaults to 0.
+    adjust: Option<i64>,
 }
 
 #[derive(Debug, FromVariant)]
@@ -158,33 +271,97 @@ pub struct ParselyWriteFieldReceiver {
 pub struct ParselyWriteVariantReceiver {
     ident: syn::Ident,
     discriminant: Option<syn::Expr>,
-    id: syn::Expr,
+    #[darling(flatten)]
+    common: ParselyCommonVariantReceiver,
     fields: ast::Fields<ParselyWriteFieldReceiver>,
 }
 
 #[derive(Debug, FromDeriveInput)]
-#[darling(attributes(parsely, parsely_read), supports(struct_any, enum_any))]
+#[darling(
+    attributes(parsely, parsely_read),
+    supports(struct_any, enum_any),
+    forward_attrs(repr)
+)]
 pub struct ParselyReadReceiver {
     ident: syn::Ident,
+    /// The type's `#[repr]` attribute, if it has one
+    attrs: Vec<syn::Attribute>,
     #[darling(default)]
     required_context: TypedFnArgList,
-    alignment: Option<usize>,
+    alignment: Option<Alignment>,
+    /// An optional total size in bytes that a struct is padded to
+    pad_to_bytes: Option<syn::Expr>,
+    /// A function or closure which validates the whole value after it's read and before it's
+    /// written
+    validate: Option<Validation>,
+    /// Expressions which are evaluated before and after the value is read or written.  They can
+    /// refer to `buf`, the context and the value (`value` after it's read, `self` when it's
+    /// written).
+    pre_read: Option<syn::Expr>,
+    post_read: Option<syn::Expr>,
+    pre_write: Option<syn::Expr>,
+    post_write: Option<syn::Expr>,
+    /// A 'magic' byte sequence which comes first in the buffer
+    magic: Option<syn::LitByteStr>,
+    /// Constant values which come after all of a struct's fields
+    #[darling(default)]
+    trailing_const_field: ConstFields,
     // Enums require a type to denote the tag type that determines which variant will be read
     key_type: Option<syn::Type>,
+    /// Values that need to be passed as context when reading an enum's tag
+    key_context: Option<Context>,
+    /// An expression to match an enum's variants against, instead of reading a tag from the buffer
+    key: Option<syn::Expr>,
+    /// Read an enum's tag without consuming it, so that it can be read again as part of the variant
+    #[darling(default)]
+    peek_key: bool,
     data: ast::Data<ParselyReadVariantReceiver, ParselyReadFieldReceiver>,
 }
 
 #[derive(Debug, FromDeriveInput)]
-#[darling(attributes(parsely, parsely_write), supports(struct_any, enum_any))]
+#[darling(
+    attributes(parsely, parsely_write),
+    supports(struct_any, enum_any),
+    forward_attrs(repr)
+)]
 pub struct ParselyWriteReceiver {
     ident: syn::Ident,
+    /// The type's `#[repr]` attribute, if it has one
+    attrs: Vec<syn::Attribute>,
     #[darling(default)]
     required_context: TypedFnArgList,
     #[darling(default)]
     sync_args: TypedFnArgList,
-    alignment: Option<usize>,
-    // Enums require a type to denote the tag type to be written to denote the variant
+    alignment: Option<Alignment>,
+    /// An optional total size in bytes that a struct is padded to
+    pad_to_bytes: Option<syn::Expr>,
+    /// A function or closure which validates the whole value after it's read and before it's
+    /// written
+    validate: Option<Validation>,
+    /// Expressions which are evaluated before and after the value is read or written.  They can
+    /// refer to `buf`, the context and the value (`value` after it's read, `self` when it's
+    /// written).
+    pre_read: Option<syn::Expr>,
+    post_read: Option<syn::Expr>,
+    pre_write: Option<syn::Expr>,
+    post_write: Option<syn::Expr>,
+    /// A 'magic' byte sequence which comes first in the buffer
+    magic: Option<syn::LitByteStr>,
+    /// Constant values which come after all of a struct's fields
+    #[darling(default)]
+    trailing_const_field: ConstFields,
+    // Enums require a type to denote the tag type to be written to denote the variant, unless
+    // their tag isn't held in the buffer (see 'key' on ParselyReadReceiver).
     key_type: Option<syn::Type>,
+    /// Values that need to be passed as context when writing an enum's tag
+    key_context: Option<Context>,
+    /// The expression that an enum's variants are matched against when reading (see
+    /// ParselyReadReceiver).  Its tag isn't in the buffer, so no tag is written unless a
+    /// 'key_type' is given.
+    key: Option<syn::Expr>,
+    /// An enum's tag is written as part of its variant, so it isn't written separately
+    #[darling(default)]
+    peek_key: bool,
     data: ast::Data<ParselyWriteVariantReceiver, ParselyWriteFieldReceiver>,
 }
 
@@ -77,6 +77,10 @@ impl Context {
     pub(crate) fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
 }
 
 impl FromMeta for Context {
@@ -145,6 +149,7 @@ impl ToTokens for TypedFnArg {
 
 /// [`syn::Local`] exists but doesn't have its own parse method, it get parsed as part of
 /// [`syn::Stmt`]
+#[allow(dead_code)]
 pub(crate) struct Local(pub(crate) syn::Local);
 
 impl Parse for Local {
@@ -239,37 +244,42 @@ impl FromMeta for MapExpr {
 }
 
 impl MapExpr {
+    /// Generate tokens which apply this map to the result of a read chain
     pub(crate) fn to_read_map_tokens(&self, field_name: &syn::Member, tokens: &mut TokenStream) {
-        let crate_name = get_crate_name();
         let field_name_string = field_name.as_friendly_string();
         let map_expr = &self.0;
-        // TODO: is there a case where context might be required for reading the 'buffer_type'
-        // value?
         tokens.extend(quote! {
-            {
-                let original_value = ::#crate_name::ParselyRead::read::<T>(buf, ())
-                    .with_context(|| format!("Reading raw value for field '{}'", #field_name_string))?;
+            .and_then(|original_value| {
                 (#map_expr)(original_value).into_parsely_result()
                     .with_context(|| format!("Mapping raw value for field '{}'", #field_name_string))
-            }
+            })
         })
     }
 
-    pub(crate) fn to_write_map_tokens(&self, field_ident: &syn::Member, tokens: &mut TokenStream) {
-        let crate_name = get_crate_name();
+    /// Generate statements which apply this map to `value` (a reference expression) and store the
+    /// result in `mapped_value`.  If this is the last map to be applied, the result must be
+    /// writable.
+    pub(crate) fn to_write_map_tokens(
+        &self,
+        field_ident: &syn::Member,
+        value: &TokenStream,
+        last: bool,
+        tokens: &mut TokenStream,
+    ) {
         let field_name_string = field_ident.as_friendly_string();
         let map_expr = &self.0;
+        let into_result = if last {
+            // Coerce the result of the mapping function into a ParselyResult<T> where we know
+            // T is writable to the buffer.  We need to use this syntax because otherwise the
+            // compiler gets caught up on trying to infer the buffer type.
+            quote! { <_ as IntoWritableParselyResult<_, B>>::into_writable_parsely_result(mapped_value) }
+        } else {
+            quote! { mapped_value.into_parsely_result() }
+        };
         tokens.extend(quote! {
-            {
-                let mapped_value = (#map_expr)(&self.#field_ident);
-                // Coerce the result of the mapping function into a ParselyResult<T> where we know
-                // T is writable to the buffer.  We need to use this syntax because otherwise the
-                // compiler gets caught up on trying to infer the buffer type.
-                let result = <_ as IntoWritableParselyResult<_, B>>::into_writable_parsely_result(mapped_value)
-                    .with_context(|| format!("Mapping raw value for field '{}'", #field_name_string))?;
-                ::#crate_name::ParselyWrite::write::<T>(&result, buf, ())
-                    .with_context(|| format!("Writing mapped value for field '{}'", #field_name_string))?;
-            }
+            let mapped_value = (#map_expr)(#value);
+            let mapped_value = #into_result
+                .with_context(|| format!("Mapping raw value for field '{}'", #field_name_string))?;
         })
     }
 }
@@ -291,6 +301,19 @@ impl FromMeta for Assertion {
 }
 
 impl Assertion {
+    /// Generate statements which check this assertion against `value` (a reference expression),
+    /// describing the value as `description` if it fails
+    pub(crate) fn to_check_tokens(&self, description: &str, value: &TokenStream) -> TokenStream {
+        let assertion = &self.0;
+        let assertion_string = quote! { #assertion }.to_string();
+        quote! {
+            let assertion_func = #assertion;
+            if !assertion_func(#value) {
+                bail!("Assertion failed: {} ('{:?}') didn't pass assertion: '{}'", #description, #value, #assertion_string)
+            }
+        }
+    }
+
     pub(crate) fn to_read_assertion_tokens(&self, field_name: &str, tokens: &mut TokenStream) {
         let assertion = &self.0;
         let assertion_string = quote! { #assertion }.to_string();
@@ -305,9 +328,11 @@ impl Assertion {
         });
     }
 
+    /// Generate statements which check this assertion against `value` (a reference expression)
     pub(crate) fn to_write_assertion_tokens(
         &self,
         field_ident: &syn::Member,
+        value: &TokenStream,
         tokens: &mut TokenStream,
     ) {
         let assertion = &self.0;
@@ -317,9 +342,856 @@ impl Assertion {
         let field_name_str = field_ident.as_friendly_string();
         tokens.extend(quote! {
             let #assertion_func_ident = #assertion;
-            if !#assertion_func_ident(&self.#field_ident) {
-                bail!("Assertion failed: value of field '{}' ('{:?}') didn't pass assertion: '{}'", #field_name_str, self.#field_ident, #assertion_string)
+            if !#assertion_func_ident(#value) {
+                bail!("Assertion failed: value of field '{}' ('{:?}') didn't pass assertion: '{}'", #field_name_str, #value, #assertion_string)
             }
         })
     }
 }
+
+/// A validation function or closure for a struct or enum, which is given a reference to the whole
+/// value after it's been read and before it's written.  It returns either a `bool` or a `Result`.
+#[derive(Debug)]
+pub(crate) struct Validation(FuncOrClosure);
+
+impl FromMeta for Validation {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Ok(Self(FuncOrClosure::from_string(value)?))
+    }
+}
+
+impl Validation {
+    /// Generate statements which validate `value` (a reference expression), a value of the type
+    /// `type_name`
+    pub(crate) fn to_check_tokens(
+        &self,
+        type_name: &syn::Ident,
+        value: TokenStream,
+    ) -> TokenStream {
+        let crate_name = get_crate_name();
+        let validation = &self.0;
+        let validation_string = quote! { #validation }.to_string();
+        let type_name_string = type_name.to_string();
+        quote! {
+            if !::#crate_name::run_validation(#value, #validation)
+                .with_context(|| format!("Validating '{}'", #type_name_string))?
+            {
+                bail!("Validation failed: '{}' didn't pass validation: '{}'", #type_name_string, #validation_string);
+            }
+        }
+    }
+}
+
+/// A step that is applied to a field's value after reading it or before writing it.
+#[derive(Debug)]
+pub(crate) enum FieldStep {
+    Assertion(Assertion),
+    Map(MapExpr),
+}
+
+/// The assertions and maps for a field (or for each element of a collection field), in the order
+/// they were declared.  Steps are applied in this order, so an assertion declared before a map sees
+/// the 'raw' value while one declared after it sees the mapped value.
+#[derive(Debug, Default)]
+pub(crate) struct FieldSteps(Vec<FieldStep>);
+
+impl FieldSteps {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn has_map(&self) -> bool {
+        self.0.iter().any(|s| matches!(s, FieldStep::Map(_)))
+    }
+
+    /// Generate tokens which apply these steps to the result of a read chain
+    pub(crate) fn to_read_tokens(&self, field_ident: &syn::Member, tokens: &mut TokenStream) {
+        for step in &self.0 {
+            match step {
+                FieldStep::Assertion(assertion) => {
+                    assertion.to_read_assertion_tokens(&field_ident.as_friendly_string(), tokens)
+                }
+                FieldStep::Map(map_expr) => map_expr.to_read_map_tokens(field_ident, tokens),
+            }
+        }
+    }
+
+    /// Generate statements which apply these steps to `value` (a reference expression) before it's
+    /// written.  Returns an expression referring to the value that should be written: each map
+    /// produces a new 'mapped_value' which the following steps operate on.
+    pub(crate) fn to_write_tokens(
+        &self,
+        field_ident: &syn::Member,
+        mut value: TokenStream,
+        tokens: &mut TokenStream,
+    ) -> TokenStream {
+        let last_map_index = self.0.iter().rposition(|s| matches!(s, FieldStep::Map(_)));
+        for (idx, step) in self.0.iter().enumerate() {
+            match step {
+                FieldStep::Assertion(assertion) => {
+                    assertion.to_write_assertion_tokens(field_ident, &value, tokens)
+                }
+                FieldStep::Map(map_expr) => {
+                    map_expr.to_write_map_tokens(
+                        field_ident,
+                        &value,
+                        Some(idx) == last_map_index,
+                        tokens,
+                    );
+                    value = quote! { &mapped_value };
+                }
+            }
+        }
+        value
+    }
+}
+
+/// All of the assertions and maps declared for a field: those for the field itself (`assertion`
+/// and `map`) and those for each element of a collection field (`elem_assertion` and `elem_map`).
+#[derive(Debug, Default)]
+pub(crate) struct DeclaredSteps {
+    pub(crate) field: FieldSteps,
+    pub(crate) elem: FieldSteps,
+}
+
+impl FromMeta for DeclaredSteps {
+    // This is parsed via 'flatten', so it's given all the items that weren't claimed by other
+    // fields in the order they appeared.
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        let mut errors = darling::Error::accumulator();
+        let mut steps = DeclaredSteps::default();
+        for item in items {
+            let meta = match item {
+                ast::NestedMeta::Meta(meta) => meta,
+                ast::NestedMeta::Lit(lit) => {
+                    errors.push(darling::Error::unexpected_lit_type(lit));
+                    continue;
+                }
+            };
+            let (target, step) = if meta.path().is_ident("assertion") {
+                (
+                    &mut steps.field,
+                    Assertion::from_meta(meta).map(FieldStep::Assertion),
+                )
+            } else if meta.path().is_ident("map") {
+                (
+                    &mut steps.field,
+                    MapExpr::from_meta(meta).map(FieldStep::Map),
+                )
+            } else if meta.path().is_ident("elem_assertion") {
+                (
+                    &mut steps.elem,
+                    Assertion::from_meta(meta).map(FieldStep::Assertion),
+                )
+            } else if meta.path().is_ident("elem_map") {
+                (
+                    &mut steps.elem,
+                    MapExpr::from_meta(meta).map(FieldStep::Map),
+                )
+            } else {
+                errors.push(darling::Error::unknown_field_path(meta.path()).with_span(meta));
+                continue;
+            };
+            if let Some(step) = errors.handle(step) {
+                target.0.push(step);
+            }
+        }
+
+        errors.finish_with(steps)
+    }
+}
+
+/// The value that identifies an enum variant in the buffer.
+#[derive(Debug, Clone)]
+pub(crate) enum VariantId {
+    /// An explicit `id`, which is used as a pattern when matching the enum's key
+    Pattern(syn::Expr),
+    /// The variant's discriminant, which the enum's key is compared against
+    Discriminant(syn::Expr),
+}
+
+/// What's declared about a variant that determines its [`VariantId`]
+pub(crate) struct VariantIdDecl<'a> {
+    pub(crate) ident: &'a syn::Ident,
+    /// The variant's explicit `id`, if any
+    pub(crate) id: Option<syn::Expr>,
+    /// The variant's explicit discriminant, if any
+    pub(crate) discriminant: Option<syn::Expr>,
+    /// Set for an unknown variant which holds the key in a field, so doesn't need an id
+    pub(crate) holds_key: bool,
+}
+
+/// The integer value of `expr` if it's a literal, so that ids can be compared
+fn literal_int(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse().ok(),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Byte(byte),
+            ..
+        }) => Some(byte.value().into()),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_int(expr).map(|value| -value),
+        syn::Expr::Paren(paren) => literal_int(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Whether a variant's id is a pattern which can match several values
+fn is_pattern(id: &syn::Expr) -> bool {
+    matches!(
+        id,
+        syn::Expr::Range(_)
+            | syn::Expr::Infer(_)
+            | syn::Expr::Binary(syn::ExprBinary {
+                op: syn::BinOp::BitOr(_),
+                ..
+            })
+            | syn::Expr::Struct(syn::ExprStruct {
+                rest: None,
+                dot2_token: Some(_),
+                ..
+            })
+    )
+}
+
+/// A description of `expr` if it's made up of literals, so that ids can be compared
+fn literal_id(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Tuple(tuple) => {
+            let elements = tuple
+                .elems
+                .iter()
+                .map(literal_id)
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("({})", elements.join(", ")))
+        }
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Bool(_) | syn::Lit::Char(_) | syn::Lit::Str(_),
+            ..
+        }) => Some(expr.to_token_stream().to_string()),
+        _ => literal_int(expr).map(|value| value.to_string()),
+    }
+}
+
+impl VariantId {
+    /// Determine the ids for an enum's variants.  A variant without an `id` uses its discriminant
+    /// which, if it isn't declared, is one more than the previous variant's id (or 0 for the first
+    /// variant).  Those implicit discriminants are only used when `implicit_discriminants` is set
+    /// (i.e. the enum is fieldless or has a `#[repr]`, so its variants have discriminants in
+    /// Rust): otherwise every variant needs an `id` or a discriminant.  Returns `None` for an
+    /// unknown variant that holds the key.
+    pub(crate) fn assign<'a>(
+        enum_name: &syn::Ident,
+        variants: impl IntoIterator<Item = VariantIdDecl<'a>>,
+        implicit_discriminants: bool,
+    ) -> anyhow::Result<Vec<Option<VariantId>>> {
+        // The previous variant's id (or discriminant), and its value if it's a literal
+        let mut previous: Option<(syn::Expr, Option<i128>)> = None;
+        let mut literal_ids: Vec<(String, &syn::Ident)> = Vec::new();
+        let mut ids = Vec::new();
+        for variant in variants {
+            let (id, value) = match (variant.id, variant.discriminant) {
+                (Some(id), _) => (VariantId::Pattern(id), None),
+                (None, Some(discriminant)) => (VariantId::Discriminant(discriminant), None),
+                (None, None)
+                    if variant.holds_key
+                        && previous.as_ref().is_some_and(|(p, _)| is_pattern(p)) =>
+                {
+                    // This variant doesn't need an id, and the next one can't count on from a
+                    // pattern either
+                    ids.push(None);
+                    continue;
+                }
+                (None, None) if implicit_discriminants || variant.holds_key => match previous {
+                    Some((ref previous, _)) if is_pattern(previous) => anyhow::bail!(
+                        "Variant '{}' of enum '{enum_name}' needs an 'id' or a discriminant: the \
+                        previous variant's id is a pattern, so it can't be counted on from",
+                        variant.ident
+                    ),
+                    Some((ref previous, previous_value)) => (
+                        VariantId::Discriminant(syn::parse_quote! { (#previous) + 1 }),
+                        previous_value.map(|v| v + 1),
+                    ),
+                    None => (VariantId::Discriminant(syn::parse_quote! { 0 }), Some(0)),
+                },
+                (None, None) => anyhow::bail!(
+                    "Variant '{}' of enum '{enum_name}' needs an 'id' or a discriminant (which \
+                    can only be implicit if the enum is fieldless or has a #[repr])",
+                    variant.ident
+                ),
+            };
+            let value = value.or_else(|| literal_int(id.value()));
+            previous = Some((id.value().clone(), value));
+            if variant.holds_key {
+                ids.push(None);
+                continue;
+            }
+            let literal = value
+                .map(|v| v.to_string())
+                .or_else(|| literal_id(id.value()));
+            if let Some(literal) = literal {
+                if let Some((_, other)) = literal_ids.iter().find(|(l, _)| *l == literal) {
+                    anyhow::bail!(
+                        "Variants '{other}' and '{}' of enum '{enum_name}' have the same id \
+                        ({literal})",
+                        variant.ident
+                    );
+                }
+                literal_ids.push((literal, variant.ident));
+            }
+            ids.push(Some(id));
+        }
+        Ok(ids)
+    }
+
+    /// The value of this id, e.g. to write as an enum's tag
+    pub(crate) fn value(&self) -> &syn::Expr {
+        match self {
+            VariantId::Pattern(expr) | VariantId::Discriminant(expr) => expr,
+        }
+    }
+
+    /// Whether this id is a pattern which can match several values, so it can't be written
+    pub(crate) fn is_pattern(&self) -> bool {
+        is_pattern(self.value())
+    }
+
+    /// Generate the pattern (and guard, if needed) for a match arm on `match_value` which matches
+    /// this id
+    pub(crate) fn to_match_arm_tokens(&self) -> TokenStream {
+        match self {
+            VariantId::Pattern(pattern) => quote! { #pattern },
+            VariantId::Discriminant(discriminant) => {
+                quote! { match_value if match_value == (#discriminant) }
+            }
+        }
+    }
+}
+
+/// A constant value of a given type that's held in the buffer but not stored in the Rust type.
+/// It's checked when reading and written when writing.
+#[derive(Debug)]
+pub(crate) struct ConstField {
+    ty: syn::Type,
+    value: syn::Expr,
+}
+
+/// Returns the given expression's integer literal if it's an integer literal without a suffix
+fn unsuffixed_int(expr: &syn::Expr) -> Option<&syn::LitInt> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) if int.suffix().is_empty() => Some(int),
+        _ => None,
+    }
+}
+
+impl ConstField {
+    /// Generate an expression which converts the constant's value to its type.  Unsuffixed
+    /// integer literals are given a type first, since the non-standard width types can only be
+    /// converted from specific integer types.
+    fn value_tokens(&self) -> TokenStream {
+        let ty = &self.ty;
+        let value = match unsuffixed_int(&self.value) {
+            Some(int) => {
+                let lit = syn::LitInt::new(&format!("{}u64", int.base10_digits()), int.span());
+                quote! { #lit }
+            }
+            None => {
+                let value = &self.value;
+                quote! { #value }
+            }
+        };
+        quote! {
+            <#ty as TryFrom<_>>::try_from(#value).map_err(|e| anyhow!(e)).context("Converting constant value")?
+        }
+    }
+}
+
+/// Constant values in the order they were declared, e.g. `const_field(u2 = 2, u1 = 0)`.  On a field
+/// they come before it in the buffer, and on a struct (`trailing_const_field`) they come after all
+/// of its fields.
+#[derive(Debug, Default)]
+pub(crate) struct ConstFields(Vec<ConstField>);
+
+impl FromMeta for ConstFields {
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        let const_fields = items
+            .iter()
+            .map(|item| match item {
+                ast::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => Ok(ConstField {
+                    ty: syn::Type::Path(syn::TypePath {
+                        qself: None,
+                        path: name_value.path.clone(),
+                    }),
+                    value: name_value.value.clone(),
+                }),
+                _ => Err(darling::Error::unsupported_format(
+                    "Constants must be given as 'type = value'",
+                )
+                .with_span(item)),
+            })
+            .collect::<darling::Result<Vec<_>>>()?;
+        Ok(Self(const_fields))
+    }
+}
+
+impl ConstFields {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Generate statements which read each constant and check it has the expected value.
+    /// `description` describes where the constants are, for error messages.
+    pub(crate) fn to_read_tokens(&self, description: &str) -> TokenStream {
+        let reads = self.0.iter().map(|const_field| {
+            let ty = &const_field.ty;
+            let value = const_field.value_tokens();
+            let const_description = format!(
+                "{description} ({} = {})",
+                quote! { #ty },
+                const_field.value.to_token_stream()
+            );
+            let assertion = Assertion(FuncOrClosure::Closure(
+                syn::parse_quote! { |read_value: &#ty| *read_value == expected_value },
+            ));
+            let check = assertion.to_check_tokens(&const_description, &quote! { &read_value });
+            quote! {
+                {
+                    let expected_value: #ty = #value;
+                    let read_value = <#ty>::read::<T>(buf, ()).with_context(|| format!("Reading {}", #description))?;
+                    #check
+                }
+            }
+        });
+        quote! {
+            #(#reads)*
+        }
+    }
+
+    /// Generate statements which write each constant
+    pub(crate) fn to_write_tokens(&self, description: &str) -> TokenStream {
+        let crate_name = get_crate_name();
+        let writes = self.0.iter().map(|const_field| {
+            let ty = &const_field.ty;
+            let value = const_field.value_tokens();
+            quote! {
+                {
+                    let value: #ty = #value;
+                    ::#crate_name::ParselyWrite::write::<T>(&value, buf, ()).with_context(|| format!("Writing {}", #description))?;
+                }
+            }
+        });
+        quote! {
+            #(#writes)*
+        }
+    }
+}
+
+/// The arguments of the list form of the `alignment` attribute
+#[derive(Debug, FromMeta)]
+struct AlignmentArgs {
+    bytes: Option<usize>,
+    bits: Option<usize>,
+    offset: Option<syn::Expr>,
+    #[darling(default)]
+    relative_to_message: bool,
+    pad: Option<syn::Expr>,
+}
+
+/// An alignment for a field, variant, struct or enum: after it's read or written, padding is
+/// consumed or added until its end is a multiple of the alignment from the alignment's origin.
+///
+/// This is given either as a number of bytes (`alignment = 4`) or as a list, e.g.
+/// `alignment(bits = 8, relative_to_message, pad = 0xff)`.  By default the origin is the start of
+/// the aligned element.  `relative_to_message` moves it to the start of the enclosing struct or
+/// enum and `offset` moves it back by the given number of bits.
+#[derive(Debug)]
+pub(crate) struct Alignment {
+    pub(crate) bits: usize,
+    pub(crate) offset: Option<syn::Expr>,
+    pub(crate) relative_to_message: bool,
+    pub(crate) pad: Option<syn::Expr>,
+}
+
+impl Alignment {
+    fn from_bytes(bytes: usize) -> darling::Result<Self> {
+        if bytes == 0 {
+            return Err(darling::Error::custom("Alignment must be non-zero"));
+        }
+        Ok(Self {
+            bits: bytes * 8,
+            offset: None,
+            relative_to_message: false,
+            pad: None,
+        })
+    }
+
+    /// Generate an expression for the number of bits between the alignment's origin and the start
+    /// of the aligned element, if the origin isn't the start of the element.  `remaining_before`
+    /// is the variable holding the buffer's remaining bits at the start of the element and
+    /// `message_start` the one holding them at the start of the message.
+    pub(crate) fn origin_offset_tokens(
+        &self,
+        remaining_before: &syn::Ident,
+        message_start: &syn::Ident,
+    ) -> Option<TokenStream> {
+        let offset = self
+            .offset
+            .as_ref()
+            .map(|offset| quote! { ((#offset) as usize) });
+        let message_offset = self
+            .relative_to_message
+            .then(|| quote! { (#message_start - #remaining_before) });
+        match (offset, message_offset) {
+            (Some(offset), Some(message_offset)) => Some(quote! { #offset + #message_offset }),
+            (offset, message_offset) => offset.or(message_offset),
+        }
+    }
+
+    /// Generate an expression for the byte value to take padding bits from
+    pub(crate) fn pad_tokens(&self) -> TokenStream {
+        match self.pad {
+            Some(ref pad) => quote! { #pad },
+            None => quote! { 0u8 },
+        }
+    }
+}
+
+impl FromMeta for Alignment {
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        Self::from_bytes(usize::from_value(value)?)
+    }
+
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        let args = AlignmentArgs::from_list(items)?;
+        let bits = match (args.bytes, args.bits) {
+            (Some(bytes), None) => bytes * 8,
+            (None, Some(bits)) => bits,
+            _ => {
+                return Err(darling::Error::custom(
+                    "Alignment needs exactly one of 'bytes' or 'bits'",
+                ))
+            }
+        };
+        if bits == 0 {
+            return Err(darling::Error::custom("Alignment must be non-zero"));
+        }
+        Ok(Self {
+            bits,
+            offset: args.offset,
+            relative_to_message: args.relative_to_message,
+            pad: args.pad,
+        })
+    }
+}
+
+/// Reserved bits that come before a field in the buffer, e.g. `skip_bits = 3` or
+/// `skip_bits(bits = 3, must_be_zero)`.  They're written as zeros.  When reading they're
+/// discarded, or with `must_be_zero`, reading fails if any of them are set.
+#[derive(Debug)]
+pub(crate) struct SkipBits {
+    pub(crate) bits: usize,
+    pub(crate) must_be_zero: bool,
+}
+
+/// The arguments of the list form of the `skip_bits` attribute
+#[derive(Debug, FromMeta)]
+struct SkipBitsArgs {
+    bits: usize,
+    #[darling(default)]
+    must_be_zero: bool,
+}
+
+impl FromMeta for SkipBits {
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        Ok(Self {
+            bits: usize::from_value(value)?,
+            must_be_zero: false,
+        })
+    }
+
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        let args = SkipBitsArgs::from_list(items)?;
+        Ok(Self {
+            bits: args.bits,
+            must_be_zero: args.must_be_zero,
+        })
+    }
+}
+
+/// Trailing padding whose presence is given by a boolean flag field and whose length is given by
+/// its final byte (as in RTP and RTCP), e.g. `trailing_padding` or `trailing_padding(alignment =
+/// 4, length_bytes = "length")`.  The alignment (in bytes) is what the padding is generated to
+/// when writing.  The length is an expression giving the length of the whole struct in bytes,
+/// which bounds the data that the padding is at the end of when reading (otherwise that's the
+/// rest of the buffer).
+#[derive(Debug, Default)]
+pub(crate) struct TrailingPadding {
+    pub(crate) alignment: Option<usize>,
+    pub(crate) length_bytes: Option<syn::Expr>,
+}
+
+/// The arguments of the list form of the `trailing_padding` attribute
+#[derive(Debug, FromMeta)]
+struct TrailingPaddingArgs {
+    alignment: Option<usize>,
+    length_bytes: Option<syn::Expr>,
+}
+
+impl FromMeta for TrailingPadding {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn from_list(items: &[ast::NestedMeta]) -> darling::Result<Self> {
+        let args = TrailingPaddingArgs::from_list(items)?;
+        if args
+            .alignment
+            .is_some_and(|alignment| alignment == 0 || alignment > 255)
+        {
+            return Err(darling::Error::custom(
+                "Trailing padding alignment must be 1-255 bytes",
+            ));
+        }
+        Ok(Self {
+            alignment: args.alignment,
+            length_bytes: args.length_bytes,
+        })
+    }
+}
+
+/// The built-in checksum algorithms, by the name they're given in the `checksum` attribute and the
+/// name of their type in the `checksum` module
+const BUILTIN_CHECKSUMS: &[(&str, &str)] = &[
+    ("internet", "InternetChecksum"),
+    ("crc8", "Crc8"),
+    ("crc16", "Crc16"),
+    ("crc16_ccitt", "Crc16Ccitt"),
+    ("crc16_kermit", "Crc16Kermit"),
+    ("crc32", "Crc32"),
+    ("crc32c", "Crc32c"),
+    ("adler32", "Adler32"),
+    ("fletcher16", "Fletcher16"),
+    ("fletcher32", "Fletcher32"),
+];
+
+/// The algorithm of a checksum field: either the name of a built-in algorithm (e.g. `"crc32"`) or
+/// the path of a type which implements `Checksum`.
+#[derive(Debug)]
+pub(crate) enum ChecksumAlgorithm {
+    Builtin(syn::Ident),
+    Custom(syn::Path),
+}
+
+impl FromMeta for ChecksumAlgorithm {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        if let Some((_, type_name)) = BUILTIN_CHECKSUMS.iter().find(|(name, _)| *name == value) {
+            return Ok(ChecksumAlgorithm::Builtin(format_ident!("{type_name}")));
+        }
+        syn::parse_str::<syn::Path>(value)
+            .map(ChecksumAlgorithm::Custom)
+            .map_err(|_| {
+                darling::Error::custom(format!(
+                    "Unknown checksum algorithm '{value}': expected one of {} or the path of a type implementing Checksum",
+                    BUILTIN_CHECKSUMS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+    }
+}
+
+impl ToTokens for ChecksumAlgorithm {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            ChecksumAlgorithm::Builtin(type_name) => {
+                let crate_name = get_crate_name();
+                tokens.extend(quote! { ::#crate_name::checksum::#type_name })
+            }
+            ChecksumAlgorithm::Custom(path) => path.to_tokens(tokens),
+        }
+    }
+}
+
+/// One end of the range of a checksum
+#[derive(Debug, PartialEq)]
+pub(crate) enum ChecksumBound {
+    /// The start of the struct
+    Start,
+    /// The end of the struct
+    End,
+    /// The checksum field itself
+    This,
+    /// The given field
+    Field(syn::Member),
+}
+
+impl ChecksumBound {
+    fn parse(value: &str) -> darling::Result<Self> {
+        match value.trim() {
+            "start" => Ok(ChecksumBound::Start),
+            "end" => Ok(ChecksumBound::End),
+            "this" => Ok(ChecksumBound::This),
+            field => syn::parse_str::<syn::Member>(field)
+                .map(ChecksumBound::Field)
+                .map_err(|_| {
+                    darling::Error::custom(format!(
+                        "Invalid checksum bound '{field}': expected 'start', 'end', 'this' or a field name"
+                    ))
+                }),
+        }
+    }
+}
+
+/// The range of the struct which a checksum covers, e.g. `start..this` or `chunk_type..=data`.  A
+/// field bound refers to the start of the field, unless it's the end of an inclusive range, where
+/// it refers to the end of the field.  The bits of the checksum field itself are treated as zeros
+/// if they're in the range.
+#[derive(Debug)]
+pub(crate) struct ChecksumRange {
+    pub(crate) start: ChecksumBound,
+    pub(crate) end: ChecksumBound,
+    pub(crate) inclusive: bool,
+}
+
+impl Default for ChecksumRange {
+    fn default() -> Self {
+        Self {
+            start: ChecksumBound::Start,
+            end: ChecksumBound::This,
+            inclusive: false,
+        }
+    }
+}
+
+impl ChecksumRange {
+    /// The fields that the range's bounds refer to
+    pub(crate) fn fields(&self) -> impl Iterator<Item = &syn::Member> {
+        [&self.start, &self.end]
+            .into_iter()
+            .filter_map(|bound| match bound {
+                ChecksumBound::Field(field) => Some(field),
+                _ => None,
+            })
+    }
+}
+
+impl FromMeta for ChecksumRange {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        let (start, end, inclusive) = if let Some((start, end)) = value.split_once("..=") {
+            (start, end, true)
+        } else if let Some((start, end)) = value.split_once("..") {
+            (start, end, false)
+        } else {
+            return Err(darling::Error::custom(format!(
+                "Invalid checksum range '{value}': expected 'a..b' or 'a..=b'"
+            )));
+        };
+        let (start, end) = (ChecksumBound::parse(start)?, ChecksumBound::parse(end)?);
+        if inclusive && matches!(end, ChecksumBound::Start | ChecksumBound::End) {
+            return Err(darling::Error::custom(
+                "The end of an inclusive checksum range must be 'this' or a field",
+            ));
+        }
+        Ok(Self {
+            start,
+            end,
+            inclusive,
+        })
+    }
+}
+
+/// A checksum field's algorithm and the range of the struct it covers
+#[derive(Debug)]
+pub(crate) struct FieldChecksum {
+    pub(crate) algorithm: ChecksumAlgorithm,
+    pub(crate) range: ChecksumRange,
+}
+
+/// The name (or index, for tuple structs) of a field, given as a string
+#[derive(Debug)]
+pub(crate) struct FieldName(pub(crate) syn::Member);
+
+impl FromMeta for FieldName {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        syn::parse_str::<syn::Member>(value)
+            .map(FieldName)
+            .map_err(|_| darling::Error::custom(format!("Invalid field name '{value}'")))
+    }
+}
+
+/// A length field which is filled in with the length of another field, as it was actually written:
+/// the number of bytes written, divided by `units` and with `adjust` added.
+#[derive(Debug)]
+pub(crate) struct LengthOf {
+    pub(crate) field: syn::Member,
+    pub(crate) units: usize,
+    pub(crate) adjust: i64,
+}
+
+/// Expressions which are evaluated before and after a field or type is read or written.  They can
+/// refer to the buffer (`buf`), the context and the value being read or written and evaluate to
+/// either `()` or a `Result`.
+#[derive(Debug, Default)]
+pub(crate) struct Hooks {
+    pub(crate) pre_read: Option<syn::Expr>,
+    pub(crate) post_read: Option<syn::Expr>,
+    pub(crate) pre_write: Option<syn::Expr>,
+    pub(crate) post_write: Option<syn::Expr>,
+}
+
+impl Hooks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pre_read.is_none()
+            && self.post_read.is_none()
+            && self.pre_write.is_none()
+            && self.post_write.is_none()
+    }
+
+    /// Generate a statement which evaluates `hook` (if it's set), describing it as `description` if
+    /// it fails
+    pub(crate) fn hook_tokens(hook: Option<&syn::Expr>, description: &str) -> Option<TokenStream> {
+        let crate_name = get_crate_name();
+        hook.map(|hook| {
+            quote! {
+                ::#crate_name::IntoParselyResult::<()>::into_parsely_result(#hook)
+                    .with_context(|| #description)?;
+            }
+        })
+    }
+
+    pub(crate) fn pre_read_tokens(&self, name: &str) -> Option<TokenStream> {
+        Self::hook_tokens(self.pre_read.as_ref(), &format!("pre_read hook for {name}"))
+    }
+
+    pub(crate) fn post_read_tokens(&self, name: &str) -> Option<TokenStream> {
+        Self::hook_tokens(
+            self.post_read.as_ref(),
+            &format!("post_read hook for {name}"),
+        )
+    }
+
+    pub(crate) fn pre_write_tokens(&self, name: &str) -> Option<TokenStream> {
+        Self::hook_tokens(
+            self.pre_write.as_ref(),
+            &format!("pre_write hook for {name}"),
+        )
+    }
+
+    pub(crate) fn post_write_tokens(&self, name: &str) -> Option<TokenStream> {
+        Self::hook_tokens(
+            self.post_write.as_ref(),
+            &format!("post_write hook for {name}"),
+        )
+    }
+}
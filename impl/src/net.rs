@@ -0,0 +1,290 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{bail, Context};
+use bits_io::prelude::*;
+
+use crate::{
+    error::ParselyResult,
+    impl_stateless_sync,
+    parsely_read::{read_bytes, ParselyRead},
+    parsely_write::{write_bytes, ParselyWrite, StateSync},
+};
+
+// Addresses are always laid out as a sequence of octets: the `ByteOrder` of a read or write only
+// affects the port in a `SocketAddr`.
+
+/// An IEEE 802 MAC address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    pub const fn new(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8) -> Self {
+        Self([a, b, c, d, e, f])
+    }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+}
+
+impl From<MacAddr> for [u8; 6] {
+    fn from(mac: MacAddr) -> Self {
+        mac.0
+    }
+}
+
+impl std::fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+/// The values used to tag the address family of an [`IpAddr`] or [`SocketAddr`] in the buffer.
+/// These match the values used by STUN's (XOR-)MAPPED-ADDRESS attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AddressFamily {
+    Ipv4 = 0x01,
+    Ipv6 = 0x02,
+}
+
+impl AddressFamily {
+    fn of(addr: &IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(_) => AddressFamily::Ipv4,
+            IpAddr::V6(_) => AddressFamily::Ipv6,
+        }
+    }
+}
+
+impl TryFrom<u8> for AddressFamily {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(AddressFamily::Ipv4),
+            0x02 => Ok(AddressFamily::Ipv6),
+            v => bail!("Unknown address family: {v:#04x}"),
+        }
+    }
+}
+
+fn read_octets<B: BitBuf, const N: usize>(buf: &mut B) -> ParselyResult<[u8; N]> {
+    Ok(read_bytes(buf, N)?
+        .try_into()
+        .expect("read_bytes returns the requested number of bytes"))
+}
+
+fn read_ip_addr<B: BitBuf>(buf: &mut B, family: AddressFamily) -> ParselyResult<IpAddr> {
+    match family {
+        AddressFamily::Ipv4 => Ok(IpAddr::V4(Ipv4Addr::read::<NetworkOrder>(buf, ())?)),
+        AddressFamily::Ipv6 => Ok(IpAddr::V6(Ipv6Addr::read::<NetworkOrder>(buf, ())?)),
+    }
+}
+
+fn write_ip_addr<B: BitBufMut>(buf: &mut B, addr: &IpAddr) -> ParselyResult<()> {
+    match addr {
+        IpAddr::V4(addr) => addr.write::<NetworkOrder>(buf, ()),
+        IpAddr::V6(addr) => addr.write::<NetworkOrder>(buf, ()),
+    }
+}
+
+impl<B: BitBuf> ParselyRead<B> for Ipv4Addr {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        Ok(Ipv4Addr::from(read_octets::<_, 4>(buf)?))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for Ipv4Addr {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_bytes(buf, &self.octets())
+    }
+}
+
+impl<B: BitBuf> ParselyRead<B> for Ipv6Addr {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        Ok(Ipv6Addr::from(read_octets::<_, 16>(buf)?))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for Ipv6Addr {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_bytes(buf, &self.octets())
+    }
+}
+
+impl<B: BitBuf> ParselyRead<B> for MacAddr {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        Ok(MacAddr(read_octets::<_, 6>(buf)?))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for MacAddr {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_bytes(buf, &self.0)
+    }
+}
+
+/// An [`IpAddr`] is laid out as an 8 bit [`AddressFamily`] followed by the address.
+impl<B: BitBuf> ParselyRead<B> for IpAddr {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        let family = AddressFamily::try_from(buf.get_u8().context("Reading address family")?)?;
+        read_ip_addr(buf, family).context("Reading address")
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for IpAddr {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        buf.put_u8(AddressFamily::of(self) as u8)
+            .context("Writing address family")?;
+        write_ip_addr(buf, self).context("Writing address")
+    }
+}
+
+/// A [`SocketAddr`] is laid out as an 8 bit [`AddressFamily`], followed by a 16 bit port and then
+/// the address.  See [`MappedAddress`] and [`XorMappedAddress`] for STUN's layouts.
+impl<B: BitBuf> ParselyRead<B> for SocketAddr {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        let family = AddressFamily::try_from(buf.get_u8().context("Reading address family")?)?;
+        let port = buf.get_u16::<T>().context("Reading port")?;
+        let addr = read_ip_addr(buf, family).context("Reading address")?;
+        Ok(SocketAddr::new(addr, port))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for SocketAddr {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        let addr = self.ip();
+        buf.put_u8(AddressFamily::of(&addr) as u8)
+            .context("Writing address family")?;
+        buf.put_u16::<T>(self.port()).context("Writing port")?;
+        write_ip_addr(buf, &addr).context("Writing address")
+    }
+}
+
+/// The magic cookie that STUN's XOR-MAPPED-ADDRESS values are XORed with
+const STUN_MAGIC_COOKIE: u32 = 0x2112a442;
+
+/// A [`SocketAddr`] laid out as in STUN's MAPPED-ADDRESS attribute: a reserved byte (which is
+/// written as zero and ignored when reading), an 8 bit [`AddressFamily`], a 16 bit port and then
+/// the address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MappedAddress(pub SocketAddr);
+
+/// A [`SocketAddr`] laid out as in STUN's XOR-MAPPED-ADDRESS attribute.  This is the same as
+/// [`MappedAddress`], except that the port is XORed with the most significant 16 bits of the magic
+/// cookie and the address with the magic cookie (for IPv4) or the magic cookie followed by the
+/// message's transaction id (for IPv6).  The transaction id is passed as context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XorMappedAddress(pub SocketAddr);
+
+/// The value that an XOR-MAPPED-ADDRESS is XORed with: the magic cookie followed by
+/// `transaction_id`
+fn xor_mask(transaction_id: &[u8; 12]) -> [u8; 16] {
+    let mut mask = [0u8; 16];
+    mask[..4].copy_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    mask[4..].copy_from_slice(transaction_id);
+    mask
+}
+
+fn xor_octets<const N: usize>(mut octets: [u8; N], mask: &[u8; 16]) -> [u8; N] {
+    for (octet, mask) in octets.iter_mut().zip(mask) {
+        *octet ^= mask;
+    }
+    octets
+}
+
+/// XOR `addr` with `mask`: this both converts an address to its XOR-MAPPED-ADDRESS form and back
+fn xor_socket_addr(addr: SocketAddr, mask: &[u8; 16]) -> SocketAddr {
+    let port = addr.port() ^ u16::from_be_bytes([mask[0], mask[1]]);
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(xor_octets(ip.octets(), mask))),
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(xor_octets(ip.octets(), mask))),
+    };
+    SocketAddr::new(ip, port)
+}
+
+fn read_mapped_address<B: BitBuf, T: ByteOrder>(buf: &mut B) -> ParselyResult<SocketAddr> {
+    buf.get_u8().context("Reading reserved byte")?;
+    SocketAddr::read::<T>(buf, ())
+}
+
+fn write_mapped_address<B: BitBufMut, T: ByteOrder>(
+    buf: &mut B,
+    addr: &SocketAddr,
+) -> ParselyResult<()> {
+    buf.put_u8(0).context("Writing reserved byte")?;
+    addr.write::<T>(buf, ())
+}
+
+impl<B: BitBuf> ParselyRead<B> for MappedAddress {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        Ok(MappedAddress(read_mapped_address::<_, T>(buf)?))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for MappedAddress {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_mapped_address::<_, T>(buf, &self.0)
+    }
+}
+
+impl<B: BitBuf> ParselyRead<B> for XorMappedAddress {
+    type Ctx = ([u8; 12],);
+
+    fn read<T: ByteOrder>(buf: &mut B, (transaction_id,): Self::Ctx) -> ParselyResult<Self> {
+        let addr = read_mapped_address::<_, T>(buf)?;
+        Ok(XorMappedAddress(xor_socket_addr(
+            addr,
+            &xor_mask(&transaction_id),
+        )))
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for XorMappedAddress {
+    type Ctx = ([u8; 12],);
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, (transaction_id,): Self::Ctx) -> ParselyResult<()> {
+        let addr = xor_socket_addr(self.0, &xor_mask(&transaction_id));
+        write_mapped_address::<_, T>(buf, &addr)
+    }
+}
+
+impl_stateless_sync!(Ipv4Addr);
+impl_stateless_sync!(Ipv6Addr);
+impl_stateless_sync!(IpAddr);
+impl_stateless_sync!(SocketAddr);
+impl_stateless_sync!(MacAddr);
+impl_stateless_sync!(MappedAddress);
+impl_stateless_sync!(XorMappedAddress);
@@ -1,12 +1,89 @@
-use bits_io::prelude::*;
+use anyhow::bail;
+use bits_io::{buf::chain::Chain, prelude::*};
 
-use crate::error::ParselyResult;
+use crate::{checksum::RecordingBuf, error::ParselyResult};
 
 pub trait ParselyRead<B>: Sized {
     type Ctx;
     fn read<T: ByteOrder>(buf: &mut B, ctx: Self::Ctx) -> ParselyResult<Self>;
 }
 
+/// Read `count` bytes from the buffer, which need not be byte-aligned.
+pub(crate) fn read_bytes<B: BitBuf>(buf: &mut B, count: usize) -> ParselyResult<Vec<u8>> {
+    // 'count' may come from the buffer itself, so check it before allocating anything
+    if count > buf.remaining_bytes() {
+        bail!(
+            "Can't read {count} bytes: only {} remaining",
+            buf.remaining_bytes()
+        );
+    }
+    let mut bytes = vec![0u8; count];
+    if buf.byte_aligned() {
+        buf.try_copy_to_slice_bytes(&mut bytes)?;
+    } else {
+        for byte in bytes.iter_mut() {
+            *byte = buf.get_u8()?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Read a value from `buf` with `read` without consuming it.  A buffer can't be rewound, so along
+/// with the value this returns a buffer which yields the bits that `read` read before continuing
+/// with the rest of `buf`: that's what should be read from afterwards.  Unlike peeking at
+/// [`BitBuf::chunk_bits`], this works when the value spans several chunks of `buf`.
+pub fn peek<B: BitBuf, V>(
+    buf: &mut B,
+    read: impl FnOnce(&mut RecordingBuf<&mut B>) -> ParselyResult<V>,
+) -> ParselyResult<(V, Chain<Bits, &mut B>)> {
+    let mut recording_buf = RecordingBuf::new(&mut *buf);
+    let value = read(&mut recording_buf)?;
+    let peeked = Bits::from(recording_buf.into_recorded());
+    Ok((value, peeked.chain(buf)))
+}
+
+/// Read the number of bits of trailing padding (as in RTP and RTCP) from the last byte of the next
+/// `region_bits` bits of `buf`, which gives the number of padding bytes at the end of the region,
+/// without consuming anything.  When the region is in `buf`'s current chunk, the byte is read in
+/// place.  Otherwise the region has to be read to get to its last byte, so it's read with
+/// [`peek`]: as with `peek`, this returns the buffer which should be read from afterwards.
+pub fn peek_trailing_padding<B: BitBuf>(
+    buf: &mut B,
+    region_bits: usize,
+) -> ParselyResult<(usize, Chain<Bits, &mut B>)> {
+    if region_bits < 8 || region_bits > buf.remaining_bits() {
+        bail!(
+            "The padding count can't be read from the end of {region_bits} bits with {} bits remaining",
+            buf.remaining_bits()
+        );
+    }
+    let chunk = buf.chunk_bits();
+    let (padding_bytes, buf) = if chunk.len() >= region_bits {
+        let padding_bytes: u8 = BigEndian::load(&chunk[region_bits - 8..region_bits]);
+        (padding_bytes, Bits::from_static_bytes(&[]).chain(buf))
+    } else {
+        peek(buf, |peek_buf| {
+            peek_buf.advance_bits(region_bits - 8);
+            Ok(peek_buf.get_u8()?)
+        })?
+    };
+    let padding_bits = usize::from(padding_bytes) * 8;
+    if padding_bits == 0 || padding_bits > region_bits {
+        bail!("Invalid padding count {padding_bytes} with {region_bits} bits of data");
+    }
+    Ok((padding_bits, buf))
+}
+
+/// Read an unsigned integer that is `bits` wide (up to 64) from the buffer.
+pub(crate) fn read_uint<B: BitBuf, T: ByteOrder>(buf: &mut B, bits: u32) -> ParselyResult<u64> {
+    if bits == 0 || bits > u64::BITS {
+        bail!("Unsupported integer width: {bits} bits");
+    }
+    let mut value = BitVec::repeat(false, bits as usize);
+    buf.try_copy_to_bit_slice(value.as_mut_bitslice())?;
+    Ok(T::load(value.as_bitslice()))
+}
+
 macro_rules! impl_parsely_read_builtin {
     ($type:ty) => {
         impl<B: BitBuf> ParselyRead<B> for $type {
@@ -1,6 +1,9 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Context};
 use bits_io::prelude::*;
 
-use crate::error::ParselyResult;
+use crate::{bounded_uint::check_uint, error::ParselyResult};
 
 /// A trait for syncing a field with any required context.  In order to prevent accidental misses
 /// of this trait, it's required for all `ParselyWrite` implementors.  When generating the
@@ -27,6 +30,88 @@ pub trait ParselyWrite<B>: StateSync + Sized {
     fn write<T: ByteOrder>(&self, buf: &mut B, ctx: Self::Ctx) -> ParselyResult<()>;
 }
 
+/// Write `bytes` to the buffer, which need not be byte-aligned.
+pub(crate) fn write_bytes<B: BitBufMut>(buf: &mut B, bytes: &[u8]) -> ParselyResult<()> {
+    if buf.byte_aligned_mut() {
+        buf.try_put_slice_bytes(bytes)?;
+    } else {
+        for byte in bytes {
+            buf.put_u8(*byte)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `value` to the buffer as an unsigned integer that is `bits` wide (up to 64).
+pub(crate) fn write_uint<B: BitBufMut, T: ByteOrder>(
+    buf: &mut B,
+    bits: u32,
+    value: u64,
+) -> ParselyResult<()> {
+    if bits == 0 || bits > u64::BITS {
+        bail!("Unsupported integer width: {bits} bits");
+    }
+    check_uint(value, bits)?;
+    let mut bits_value = BitVec::repeat(false, bits as usize);
+    T::store(bits_value.as_mut_bitslice(), value);
+    buf.try_put_bit_slice(bits_value.as_bitslice())?;
+    Ok(())
+}
+
+/// Write `value` (as a `V`) over the bits in `field`, which have already been written.  This is used
+/// to fill in fields whose values are only known once the rest of their struct has been written.
+pub fn backpatch<V, T>(buf: &mut BitsMut, field: Range<usize>, value: u64) -> ParselyResult<()>
+where
+    V: TryFrom<u64> + ParselyWrite<BitsMut, Ctx = ()>,
+    <V as TryFrom<u64>>::Error: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    T: ByteOrder,
+{
+    if field.start > field.end || field.end > buf.len_bits() {
+        bail!(
+            "Field range {field:?} is invalid for {} bits",
+            buf.len_bits()
+        );
+    }
+    let value = V::try_from(value)
+        .map_err(|e| anyhow!(e))
+        .with_context(|| format!("Converting {value}"))?;
+    let mut value_buf = BitsMut::new();
+    value.write::<T>(&mut value_buf, ())?;
+    if value_buf.len_bits() != field.len() {
+        bail!(
+            "Value was written as {} bits, but the field has {} bits",
+            value_buf.len_bits(),
+            field.len()
+        );
+    }
+    buf[field].copy_from_bitslice(&value_buf);
+    Ok(())
+}
+
+/// Write the length of the bits in `measured`, in `units` bytes and with `adjust` added, over the
+/// bits in `field` (as a `V`).
+pub fn backpatch_length<V, T>(
+    buf: &mut BitsMut,
+    measured: Range<usize>,
+    units: usize,
+    adjust: i64,
+    field: Range<usize>,
+) -> ParselyResult<()>
+where
+    V: TryFrom<u64> + ParselyWrite<BitsMut, Ctx = ()>,
+    <V as TryFrom<u64>>::Error: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    T: ByteOrder,
+{
+    let bits = measured.len();
+    if !bits.is_multiple_of(8 * units) {
+        bail!("Length of {bits} bits isn't a whole number of {units} byte units");
+    }
+    let length = (bits / (8 * units)) as i64 + adjust;
+    let length =
+        u64::try_from(length).map_err(|_| anyhow!("Adjusted length {length} is negative"))?;
+    backpatch::<V, T>(buf, field, length)
+}
+
 macro_rules! impl_parsely_write_builtin {
     ($type:ty) => {
         impl<B: BitBufMut> ParselyWrite<B> for $type {
@@ -0,0 +1,88 @@
+use anyhow::{bail, Context};
+use bits_io::prelude::*;
+
+use crate::{
+    bounded_uint::{check_uint, checked_width, max_uint},
+    error::ParselyResult,
+    parsely_read::{read_uint, ParselyRead},
+    parsely_write::{write_uint, ParselyWrite, StateSync},
+};
+
+/// `N` reserved bits.  The bits that were read are preserved, so a value that was read will be
+/// written back unchanged, while a [`Default`] value writes zeros.  Use [`Reserved::is_zero`] in an
+/// assertion to require that the bits are zero when reading.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reserved<const N: u32>(u64);
+
+impl<const N: u32> Reserved<N> {
+    pub const BITS: u32 = checked_width(N);
+    pub const MAX_RAW: u64 = max_uint(Self::BITS);
+
+    pub fn try_from_raw(raw: u64) -> ParselyResult<Self> {
+        Ok(Self(check_uint(raw, Self::BITS)?))
+    }
+
+    pub const fn raw(&self) -> u64 {
+        self.0
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Skip `count` reserved bits (the `skip_bits` attribute).  If `must_be_zero` is set, this fails
+/// if any of them are set.
+pub fn skip_reserved_bits<B: BitBuf, T: ByteOrder>(
+    buf: &mut B,
+    count: usize,
+    must_be_zero: bool,
+) -> ParselyResult<()> {
+    let mut remaining = count;
+    while remaining > 0 {
+        let bits = remaining.min(u64::BITS as usize);
+        let value = read_uint::<B, T>(buf, bits as u32)?;
+        if must_be_zero && value != 0 {
+            bail!("Reserved bits must be zero, got {value:#x}");
+        }
+        remaining -= bits;
+    }
+    Ok(())
+}
+
+/// Write `count` reserved bits as zeros (the `skip_bits` attribute)
+pub fn write_reserved_bits<B: BitBufMut, T: ByteOrder>(
+    buf: &mut B,
+    count: usize,
+) -> ParselyResult<()> {
+    let mut remaining = count;
+    while remaining > 0 {
+        let bits = remaining.min(u64::BITS as usize);
+        write_uint::<B, T>(buf, bits as u32, 0)?;
+        remaining -= bits;
+    }
+    Ok(())
+}
+
+impl<B: BitBuf, const N: u32> ParselyRead<B> for Reserved<N> {
+    type Ctx = ();
+
+    fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+        let raw =
+            read_uint::<B, T>(buf, Self::BITS).with_context(|| format!("Reading Reserved<{N}>"))?;
+        Ok(Self(raw))
+    }
+}
+
+impl<B: BitBufMut, const N: u32> ParselyWrite<B> for Reserved<N> {
+    type Ctx = ();
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+        write_uint::<B, T>(buf, Self::BITS, self.0)
+            .with_context(|| format!("Writing Reserved<{N}>"))
+    }
+}
+
+impl<const N: u32> StateSync for Reserved<N> {
+    type SyncCtx = ();
+}
@@ -0,0 +1,289 @@
+use anyhow::{anyhow, bail, Context};
+use bits_io::prelude::*;
+
+use crate::{
+    error::ParselyResult,
+    parsely_read::{read_bytes, read_uint, ParselyRead},
+    parsely_write::{write_bytes, write_uint, ParselyWrite},
+};
+
+/// Describes how the extent of a string is determined in the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringFormat {
+    /// The string is preceded by an unsigned integer of the given width (in bits) which holds the
+    /// length of the string in bytes.  The width of a type can be passed via its `BITS` constant,
+    /// e.g. `StringFormat::LengthPrefixed(u16::BITS)`.
+    LengthPrefixed(u32),
+    /// The string is followed by a NUL character (a single zero code unit).
+    NulTerminated,
+    /// The string occupies exactly `len` bytes, with any unused trailing bytes filled with `pad`.
+    Fixed { len: usize, pad: u8 },
+    /// The string takes up the rest of the buffer.
+    ToEnd,
+}
+
+/// The character encoding used for a string in the buffer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    #[default]
+    Utf8,
+    /// 7-bit ASCII.
+    Ascii,
+    /// ISO 8859-1, where each byte maps directly to the code point of the same value.
+    Latin1,
+    /// UTF-16, with each code unit laid out according to the `ByteOrder` the read or write is
+    /// done with.
+    Utf16,
+}
+
+/// Describes how data that isn't valid in the string's encoding should be handled when reading a
+/// string.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StringValidation {
+    /// Invalid data results in an error.
+    #[default]
+    Strict,
+    /// Invalid sequences are replaced with `U+FFFD`.
+    Lossy,
+}
+
+impl StringEncoding {
+    /// The size, in bytes, of a single code unit in this encoding
+    fn unit_bytes(&self) -> usize {
+        match self {
+            StringEncoding::Utf16 => 2,
+            _ => 1,
+        }
+    }
+
+    fn decode<T: ByteOrder>(
+        &self,
+        bytes: Vec<u8>,
+        validation: StringValidation,
+    ) -> ParselyResult<String> {
+        let lossy = validation == StringValidation::Lossy;
+        match self {
+            StringEncoding::Utf8 if lossy => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            StringEncoding::Utf8 => Ok(String::from_utf8(bytes)?),
+            StringEncoding::Ascii => bytes
+                .into_iter()
+                .map(|b| match b {
+                    b if b.is_ascii() => Ok(b as char),
+                    _ if lossy => Ok(char::REPLACEMENT_CHARACTER),
+                    b => Err(anyhow!("Invalid ASCII byte: {b:#04x}")),
+                })
+                .collect(),
+            StringEncoding::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+            StringEncoding::Utf16 => {
+                let odd_length = !bytes.len().is_multiple_of(2);
+                if odd_length && !lossy {
+                    bail!("UTF-16 data has an odd number of bytes ({})", bytes.len());
+                }
+                let units = bytes.chunks_exact(2).map(T::load_u16).collect::<Vec<_>>();
+                let mut value = if lossy {
+                    String::from_utf16_lossy(&units)
+                } else {
+                    String::from_utf16(&units)?
+                };
+                if odd_length {
+                    value.push(char::REPLACEMENT_CHARACTER);
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn encode<T: ByteOrder>(&self, value: &str) -> ParselyResult<Vec<u8>> {
+        match self {
+            StringEncoding::Utf8 => Ok(value.as_bytes().to_vec()),
+            StringEncoding::Ascii => value
+                .chars()
+                .map(|c| match c {
+                    c if c.is_ascii() => Ok(c as u8),
+                    c => Err(anyhow!("Character {c:?} can't be encoded as ASCII")),
+                })
+                .collect(),
+            StringEncoding::Latin1 => value
+                .chars()
+                .map(|c| {
+                    u8::try_from(c)
+                        .map_err(|_| anyhow!("Character {c:?} can't be encoded as Latin-1"))
+                })
+                .collect(),
+            StringEncoding::Utf16 => Ok(value
+                .encode_utf16()
+                .flat_map(|unit| {
+                    let mut bytes = [0u8; 2];
+                    T::store_u16(&mut bytes, unit);
+                    bytes
+                })
+                .collect()),
+        }
+    }
+}
+
+/// The context used when reading or writing a [`String`] field.
+///
+/// ```ignore
+/// #[parsely(context("StringOptions::nul_terminated()"))]
+/// name: String,
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringOptions {
+    pub format: StringFormat,
+    pub encoding: StringEncoding,
+    pub validation: StringValidation,
+}
+
+impl StringOptions {
+    pub const fn new(format: StringFormat) -> Self {
+        Self {
+            format,
+            encoding: StringEncoding::Utf8,
+            validation: StringValidation::Strict,
+        }
+    }
+
+    /// A string preceded by a length (in bytes) that is `prefix_bits` wide.
+    pub const fn length_prefixed(prefix_bits: u32) -> Self {
+        Self::new(StringFormat::LengthPrefixed(prefix_bits))
+    }
+
+    pub const fn nul_terminated() -> Self {
+        Self::new(StringFormat::NulTerminated)
+    }
+
+    /// A string that takes up exactly `len` bytes, padded with NUL bytes.  When reading, the
+    /// string ends at the first NUL and anything after it is ignored.
+    pub const fn nul_padded(len: usize) -> Self {
+        Self::new(StringFormat::Fixed { len, pad: b'\0' })
+    }
+
+    /// A string that takes up exactly `len` bytes, padded with spaces
+    pub const fn space_padded(len: usize) -> Self {
+        Self::new(StringFormat::Fixed { len, pad: b' ' })
+    }
+
+    pub const fn to_end() -> Self {
+        Self::new(StringFormat::ToEnd)
+    }
+
+    /// Use the given encoding instead of UTF-8
+    pub const fn encoding(mut self, encoding: StringEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Replace invalid sequences instead of failing the read
+    pub const fn lossy(mut self) -> Self {
+        self.validation = StringValidation::Lossy;
+        self
+    }
+}
+
+impl<B: BitBuf> ParselyRead<B> for String {
+    type Ctx = (StringOptions,);
+
+    fn read<T: ByteOrder>(buf: &mut B, (options,): Self::Ctx) -> ParselyResult<Self> {
+        let bytes = match options.format {
+            StringFormat::LengthPrefixed(bits) => {
+                let length = read_uint::<B, T>(buf, bits).context("Reading length prefix")?;
+                let length = usize::try_from(length).map_err(|e| anyhow!(e))?;
+                read_bytes(buf, length).context("Reading string data")?
+            }
+            StringFormat::NulTerminated => {
+                let unit_bytes = options.encoding.unit_bytes();
+                let mut bytes = Vec::new();
+                loop {
+                    let unit =
+                        read_bytes(buf, unit_bytes).context("Reading NUL-terminated string")?;
+                    if unit.iter().all(|b| *b == 0) {
+                        break;
+                    }
+                    bytes.extend(unit);
+                }
+                bytes
+            }
+            StringFormat::Fixed { len, pad } => {
+                let mut bytes = read_bytes(buf, len).context("Reading fixed-width string")?;
+                if pad == b'\0' {
+                    // Like a C string, a NUL-padded string ends at its first NUL
+                    let unit_bytes = options.encoding.unit_bytes();
+                    if let Some(nul) = bytes
+                        .chunks(unit_bytes)
+                        .position(|unit| unit.iter().all(|b| *b == 0))
+                    {
+                        bytes.truncate(nul * unit_bytes);
+                    }
+                }
+                let value = options
+                    .encoding
+                    .decode::<T>(bytes, options.validation)
+                    .context("Decoding string data")?;
+                return Ok(value.trim_end_matches(char::from(pad)).to_owned());
+            }
+            StringFormat::ToEnd => {
+                let length = buf.remaining_bytes();
+                read_bytes(buf, length).context("Reading string data")?
+            }
+        };
+
+        options
+            .encoding
+            .decode::<T>(bytes, options.validation)
+            .context("Decoding string data")
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for String {
+    type Ctx = (StringOptions,);
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, (options,): Self::Ctx) -> ParselyResult<()> {
+        let encoded = options
+            .encoding
+            .encode::<T>(self)
+            .context("Encoding string data")?;
+        let bytes = encoded.as_slice();
+        match options.format {
+            StringFormat::LengthPrefixed(bits) => {
+                write_uint::<B, T>(buf, bits, bytes.len() as u64)
+                    .context("Writing length prefix")?;
+                write_bytes(buf, bytes).context("Writing string data")?;
+            }
+            StringFormat::NulTerminated => {
+                if self.contains('\0') {
+                    bail!("NUL-terminated string can't contain a NUL character");
+                }
+                write_bytes(buf, bytes).context("Writing string data")?;
+                write_bytes(buf, &vec![0; options.encoding.unit_bytes()])
+                    .context("Writing NUL terminator")?;
+            }
+            StringFormat::Fixed { len, pad } => {
+                if bytes.len() > len {
+                    bail!(
+                        "String length ({}) exceeds fixed width of {len} bytes",
+                        bytes.len()
+                    );
+                }
+                let pad = options
+                    .encoding
+                    .encode::<T>(char::from(pad).encode_utf8(&mut [0; 4]))
+                    .context("Encoding string padding")?;
+                if !(len - bytes.len()).is_multiple_of(pad.len()) {
+                    bail!(
+                        "Can't pad string of {} bytes to fixed width of {len} bytes",
+                        bytes.len()
+                    );
+                }
+                write_bytes(buf, bytes).context("Writing string data")?;
+                for _ in 0..(len - bytes.len()) / pad.len() {
+                    write_bytes(buf, &pad).context("Writing string padding")?;
+                }
+            }
+            StringFormat::ToEnd => {
+                write_bytes(buf, bytes).context("Writing string data")?;
+            }
+        }
+        Ok(())
+    }
+}
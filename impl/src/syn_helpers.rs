@@ -1,3 +1,4 @@
+use proc_macro2::{TokenStream, TokenTree};
 use quote::format_ident;
 
 pub(crate) trait TypeExts {
@@ -98,3 +99,67 @@ impl MemberExts for syn::Member {
         }
     }
 }
+
+/// Whether `tokens` contain `ident` anywhere (including inside groups), e.g. to find the fields an
+/// expression refers to
+pub(crate) fn refers_to(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ref token) => token == ident,
+        TokenTree::Group(ref group) => refers_to(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Some attribute names (like `as`) are Rust keywords, which means they can't be parsed as part of
+/// a [`syn::Meta`].  This renames those in all of the parsely attributes on the given item (and its
+/// fields and variants) so they can be parsed: `as` becomes `as_type`.  The internal names can't
+/// be given directly, so `as_type` is rejected.
+pub(crate) fn rename_keyword_attributes(input: &mut syn::DeriveInput) -> syn::Result<()> {
+    fn rename_in(attrs: &mut [syn::Attribute]) -> syn::Result<()> {
+        for attr in attrs {
+            let is_parsely = ["parsely", "parsely_read", "parsely_write"]
+                .iter()
+                .any(|name| attr.path().is_ident(name));
+            let syn::Meta::List(ref mut list) = attr.meta else {
+                continue;
+            };
+            if !is_parsely {
+                continue;
+            }
+            // Only the top-level tokens are attribute names: values are string literals.
+            list.tokens = std::mem::take(&mut list.tokens)
+                .into_iter()
+                .map(|tt| match tt {
+                    TokenTree::Ident(ref ident) if ident == "as" => Ok(TokenTree::Ident(
+                        proc_macro2::Ident::new("as_type", ident.span()),
+                    )),
+                    TokenTree::Ident(ref ident) if ident == "as_type" => Err(syn::Error::new(
+                        ident.span(),
+                        "Unknown attribute 'as_type': use 'as'",
+                    )),
+                    tt => Ok(tt),
+                })
+                .collect::<syn::Result<_>>()?;
+        }
+        Ok(())
+    }
+
+    rename_in(&mut input.attrs)?;
+    match input.data {
+        syn::Data::Struct(ref mut data) => {
+            for field in data.fields.iter_mut() {
+                rename_in(&mut field.attrs)?;
+            }
+        }
+        syn::Data::Enum(ref mut data) => {
+            for variant in data.variants.iter_mut() {
+                rename_in(&mut variant.attrs)?;
+                for field in variant.fields.iter_mut() {
+                    rename_in(&mut field.attrs)?;
+                }
+            }
+        }
+        syn::Data::Union(_) => {}
+    }
+    Ok(())
+}
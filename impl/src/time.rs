@@ -0,0 +1,253 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context};
+use bits_io::prelude::*;
+
+use crate::{
+    error::ParselyResult,
+    impl_stateless_sync,
+    parsely_read::{read_uint, ParselyRead},
+    parsely_write::{write_uint, ParselyWrite, StateSync},
+};
+
+/// Seconds between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01)
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Convert a fraction of a second with `bits` fractional bits into nanoseconds
+fn fraction_to_nanos(fraction: u64, bits: u32) -> u32 {
+    ((fraction * NANOS_PER_SEC) >> bits) as u32
+}
+
+/// Convert nanoseconds into a fraction of a second with `bits` fractional bits
+fn nanos_to_fraction(nanos: u32, bits: u32) -> u64 {
+    ((nanos as u64) << bits) / NANOS_PER_SEC
+}
+
+/// A 64 bit NTP timestamp: 32 bits of seconds since 1900-01-01 followed by 32 bits of fractional
+/// seconds (RFC 5905).
+///
+/// NTP timestamps wrap every 2^32 seconds.  When converting to a [`SystemTime`], timestamps with
+/// the most significant bit unset are assumed to be after the 2036 rollover (RFC 4330).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NtpTimestamp(pub u64);
+
+impl NtpTimestamp {
+    pub const fn new(seconds: u32, fraction: u32) -> Self {
+        Self(((seconds as u64) << 32) | fraction as u64)
+    }
+
+    pub const fn seconds(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    pub const fn fraction(&self) -> u32 {
+        self.0 as u32
+    }
+
+    /// The 'middle' 32 bits of this timestamp, as used in RTCP reports
+    pub const fn to_compact(&self) -> CompactNtpTimestamp {
+        CompactNtpTimestamp((self.0 >> 16) as u32)
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        let seconds = self.seconds() as i64;
+        let seconds = if seconds & 0x8000_0000 != 0 {
+            seconds
+        } else {
+            seconds + (1 << 32)
+        };
+        let nanos = fraction_to_nanos(self.fraction() as u64, 32);
+        let unix_seconds = seconds - NTP_UNIX_OFFSET_SECS;
+        if unix_seconds >= 0 {
+            UNIX_EPOCH + Duration::new(unix_seconds as u64, nanos)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(unix_seconds.unsigned_abs()) + Duration::new(0, nanos)
+        }
+    }
+}
+
+impl From<SystemTime> for NtpTimestamp {
+    fn from(time: SystemTime) -> Self {
+        let (unix_seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+            Err(e) => {
+                let before = e.duration();
+                let borrow = (before.subsec_nanos() > 0) as i64;
+                (
+                    -(before.as_secs() as i64) - borrow,
+                    (NANOS_PER_SEC as u32 - before.subsec_nanos()) % NANOS_PER_SEC as u32,
+                )
+            }
+        };
+        let seconds = (unix_seconds + NTP_UNIX_OFFSET_SECS).rem_euclid(1 << 32) as u32;
+        NtpTimestamp::new(seconds, nanos_to_fraction(nanos, 32) as u32)
+    }
+}
+
+impl From<NtpTimestamp> for SystemTime {
+    fn from(timestamp: NtpTimestamp) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+/// A 32 bit 'compact' NTP timestamp made up of the middle 32 bits of an [`NtpTimestamp`]: 16 bits
+/// of seconds followed by 16 bits of fractional seconds.  This is used for values like the
+/// 'delay since last SR' in RTCP reports, so it converts to and from a [`Duration`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactNtpTimestamp(pub u32);
+
+impl CompactNtpTimestamp {
+    pub const fn new(seconds: u16, fraction: u16) -> Self {
+        Self(((seconds as u32) << 16) | fraction as u32)
+    }
+
+    pub const fn seconds(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+
+    pub const fn fraction(&self) -> u16 {
+        self.0 as u16
+    }
+
+    pub fn to_duration(&self) -> Duration {
+        Duration::new(
+            self.seconds() as u64,
+            fraction_to_nanos(self.fraction() as u64, 16),
+        )
+    }
+}
+
+impl From<NtpTimestamp> for CompactNtpTimestamp {
+    fn from(timestamp: NtpTimestamp) -> Self {
+        timestamp.to_compact()
+    }
+}
+
+impl From<CompactNtpTimestamp> for Duration {
+    fn from(timestamp: CompactNtpTimestamp) -> Self {
+        timestamp.to_duration()
+    }
+}
+
+impl TryFrom<Duration> for CompactNtpTimestamp {
+    type Error = anyhow::Error;
+
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        let seconds = u16::try_from(duration.as_secs()).map_err(|_| {
+            anyhow!("Duration {duration:?} is too large for a compact NTP timestamp")
+        })?;
+        let fraction = nanos_to_fraction(duration.subsec_nanos(), 16) as u16;
+        Ok(CompactNtpTimestamp::new(seconds, fraction))
+    }
+}
+
+/// A 32 bit count of seconds since the Unix epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixSeconds(pub u32);
+
+impl UnixSeconds {
+    pub fn to_system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from(*self)
+    }
+}
+
+impl From<UnixSeconds> for Duration {
+    fn from(timestamp: UnixSeconds) -> Self {
+        Duration::from_secs(timestamp.0 as u64)
+    }
+}
+
+impl From<UnixSeconds> for SystemTime {
+    fn from(timestamp: UnixSeconds) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+impl TryFrom<Duration> for UnixSeconds {
+    type Error = anyhow::Error;
+
+    fn try_from(since_epoch: Duration) -> Result<Self, Self::Error> {
+        u32::try_from(since_epoch.as_secs())
+            .map(UnixSeconds)
+            .map_err(|_| anyhow!("Duration {since_epoch:?} is too large for 32 bit Unix seconds"))
+    }
+}
+
+impl TryFrom<SystemTime> for UnixSeconds {
+    type Error = anyhow::Error;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        UnixSeconds::try_from(time.duration_since(UNIX_EPOCH)?)
+    }
+}
+
+/// A 64 bit count of milliseconds since the Unix epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixMillis(pub u64);
+
+impl UnixMillis {
+    pub fn to_system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from(*self)
+    }
+}
+
+impl From<UnixMillis> for Duration {
+    fn from(timestamp: UnixMillis) -> Self {
+        Duration::from_millis(timestamp.0)
+    }
+}
+
+impl From<UnixMillis> for SystemTime {
+    fn from(timestamp: UnixMillis) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+impl TryFrom<Duration> for UnixMillis {
+    type Error = anyhow::Error;
+
+    fn try_from(since_epoch: Duration) -> Result<Self, Self::Error> {
+        u64::try_from(since_epoch.as_millis())
+            .map(UnixMillis)
+            .map_err(|_| anyhow!("Duration {since_epoch:?} is too large for 64 bit Unix millis"))
+    }
+}
+
+impl TryFrom<SystemTime> for UnixMillis {
+    type Error = anyhow::Error;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        UnixMillis::try_from(time.duration_since(UNIX_EPOCH)?)
+    }
+}
+
+macro_rules! impl_parsely_timestamp {
+    ($type:ident, $bits:expr, $inner:ty) => {
+        impl<B: BitBuf> ParselyRead<B> for $type {
+            type Ctx = ();
+
+            fn read<T: ByteOrder>(buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<Self> {
+                let value = read_uint::<B, T>(buf, $bits)
+                    .with_context(|| format!("Reading {}", stringify!($type)))?;
+                Ok($type(value as $inner))
+            }
+        }
+
+        impl<B: BitBufMut> ParselyWrite<B> for $type {
+            type Ctx = ();
+
+            fn write<T: ByteOrder>(&self, buf: &mut B, _ctx: Self::Ctx) -> ParselyResult<()> {
+                write_uint::<B, T>(buf, $bits, self.0 as u64)
+                    .with_context(|| format!("Writing {}", stringify!($type)))
+            }
+        }
+
+        impl_stateless_sync!($type);
+    };
+}
+
+impl_parsely_timestamp!(NtpTimestamp, 64, u64);
+impl_parsely_timestamp!(CompactNtpTimestamp, 32, u32);
+impl_parsely_timestamp!(UnixSeconds, 32, u32);
+impl_parsely_timestamp!(UnixMillis, 64, u64);
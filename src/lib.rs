@@ -2,14 +2,24 @@
 
 // TODO: these should be moved to a prelude file
 pub use parsely_impl::anyhow::{Context, anyhow, bail};
-pub use parsely_impl::error::{IntoParselyResult, IntoWritableParselyResult, ParselyResult};
+pub use parsely_impl::checksum::{self, Checksum, ChecksumError};
+pub use parsely_impl::error::{
+    IntoParselyResult, IntoValidationResult, IntoWritableParselyResult, ParselyResult,
+    run_validation,
+};
+pub use parsely_impl::fixed_point::{IFixed, UFixed};
 pub use parsely_impl::impl_stateless_sync;
+pub use parsely_impl::net::{AddressFamily, MacAddr, MappedAddress, XorMappedAddress};
 pub use parsely_impl::nsw_types::{from_bitslice::BitSliceUxExts, *};
+pub use parsely_impl::reserved::{self, Reserved};
+pub use parsely_impl::string::{StringEncoding, StringFormat, StringOptions, StringValidation};
+pub use parsely_impl::time::{CompactNtpTimestamp, NtpTimestamp, UnixMillis, UnixSeconds};
 pub use parsely_impl::{BigEndian, ByteOrder, LittleEndian, NetworkOrder};
 pub use parsely_impl::{BitBuf, BitBufExts, BitBufMut, BitBufMutExts, Bits, BitsMut};
 pub use parsely_impl::{BitCursor, BitRead, BitWrite};
 pub use parsely_impl::{
-    parsely_read::ParselyRead, parsely_write::ParselyWrite, parsely_write::StateSync,
+    parsely_read::{self, ParselyRead},
+    parsely_write::{self, ParselyWrite, StateSync},
 };
 pub use parsely_macro::{ParselyRead, ParselyWrite};
 
@@ -9,10 +9,14 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
         buf: &mut B,
         (): (),
     ) -> ::parsely_rs::ParselyResult<Self> {
-        let __bytes_read_before_Foo_read = buf.remaining_bytes();
-        let one = u8::read::<T>(buf, ()).with_context(|| "Reading field 'one'")?;
-        while (__bytes_read_before_Foo_read - buf.remaining_bytes()) % 4usize != 0 {
-            buf.get_u8().context("consuming padding")?;
+        let __bits_remaining_before_Foo_read = buf.remaining_bits();
+        let one = <u8>::read::<T>(buf, ()).with_context(|| "Reading field 'one'")?;
+        {
+            let offset = __bits_remaining_before_Foo_read - buf.remaining_bits();
+            let padding_bits = (32usize - offset % 32usize) % 32usize;
+            for _ in 0..padding_bits {
+                buf.get_u1().context("consuming padding")?;
+            }
         }
         Ok(Self { one })
     }
@@ -20,18 +24,22 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
 impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
     type Ctx = ();
     fn write<T: ByteOrder>(&self, buf: &mut B, (): Self::Ctx) -> ParselyResult<()> {
-        let __bytes_written_before_Foo_write = buf.remaining_mut_bytes();
-        u8::write::<T>(&self.one, buf, ())
+        let __bits_remaining_before_Foo_write = buf.remaining_mut_bits();
+        <u8>::write::<T>(&self.one, buf, ())
             .with_context(|| ::alloc::__export::must_use({
                 let res = ::alloc::fmt::format(
                     format_args!("Writing field \'{0}\'", "one"),
                 );
                 res
             }))?;
-        while (__bytes_written_before_Foo_write - buf.remaining_mut_bytes()) % 4usize
-            != 0
         {
-            buf.put_u8(0).context("adding padding")?;
+            let pad: u8 = 0u8;
+            let offset = __bits_remaining_before_Foo_write - buf.remaining_mut_bits();
+            let padding_bits = (32usize - offset % 32usize) % 32usize;
+            for i in offset..offset + padding_bits {
+                buf.put_u1(::parsely_rs::u1::new((pad >> (7 - i % 8)) & 1))
+                    .context("adding padding")?;
+            }
         }
         Ok(())
     }
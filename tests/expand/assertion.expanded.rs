@@ -9,7 +9,7 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
         buf: &mut B,
         (): (),
     ) -> ::parsely_rs::ParselyResult<Self> {
-        let value = u8::read::<T>(buf, ())
+        let value = <u8>::read::<T>(buf, ())
             .and_then(|read_value| {
                 let assertion_func = |v: &u8| *v % 2 == 0;
                 if !assertion_func(&read_value) {
@@ -44,7 +44,7 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
                         let res = ::alloc::fmt::format(
                             format_args!(
                                 "Assertion failed: value of field \'{0}\' (\'{1:?}\') didn\'t pass assertion: \'{2}\'",
-                                "value", self.value, "| v : & u8 | * v % 2 == 0",
+                                "value", & self.value, "| v : & u8 | * v % 2 == 0",
                             ),
                         );
                         res
@@ -52,7 +52,7 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
                 ),
             );
         }
-        u8::write::<T>(&self.value, buf, ())
+        <u8>::write::<T>(&self.value, buf, ())
             .with_context(|| ::alloc::__export::must_use({
                 let res = ::alloc::fmt::format(
                     format_args!("Writing field \'{0}\'", "value"),
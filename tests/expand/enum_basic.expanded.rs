@@ -37,22 +37,18 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
         (): (),
     ) -> ::parsely_rs::ParselyResult<Self> {
         let match_value = <u8 as ::parsely_rs::ParselyRead<_>>::read::<T>(buf, ())
-            .with_context(|| ::alloc::__export::must_use({
-                let res = ::alloc::fmt::format(
-                    format_args!("Tag for enum \'{0}\'", "Foo"),
-                );
-                res
-            }))?;
-        match match_value {
+            .with_context(|| "Tag for enum 'Foo'")?;
+        let value: ::parsely_rs::ParselyResult<Self> = match match_value {
             1 => Ok(Foo::One),
             2 => {
-                let field_0 = u8::read::<T>(buf, ())
+                let field_0 = <u8>::read::<T>(buf, ())
                     .with_context(|| "Reading field 'Field 0'")?;
                 Ok(Foo::Two(field_0))
             }
             3 => {
-                let bar = u8::read::<T>(buf, ()).with_context(|| "Reading field 'bar'")?;
-                let baz = u16::read::<T>(buf, ())
+                let bar = <u8>::read::<T>(buf, ())
+                    .with_context(|| "Reading field 'bar'")?;
+                let baz = <u16>::read::<T>(buf, ())
                     .with_context(|| "Reading field 'baz'")?;
                 Ok(Foo::Three { bar, baz })
             }
@@ -68,7 +64,8 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
                     }),
                 )
             }
-        }
+        };
+        value
     }
 }
 impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
@@ -82,7 +79,7 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
             Foo::Two(ref field_0) => {
                 let tag_value: u8 = 2;
                 ::parsely_rs::ParselyWrite::write::<T>(&tag_value, buf, ())?;
-                u8::write::<T>(&field_0, buf, ())
+                <u8>::write::<T>(&field_0, buf, ())
                     .with_context(|| ::alloc::__export::must_use({
                         let res = ::alloc::fmt::format(
                             format_args!("Writing field \'{0}\'", "Field 0"),
@@ -93,14 +90,14 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
             Foo::Three { ref bar, ref baz } => {
                 let tag_value: u8 = 3;
                 ::parsely_rs::ParselyWrite::write::<T>(&tag_value, buf, ())?;
-                u8::write::<T>(&bar, buf, ())
+                <u8>::write::<T>(&bar, buf, ())
                     .with_context(|| ::alloc::__export::must_use({
                         let res = ::alloc::fmt::format(
                             format_args!("Writing field \'{0}\'", "bar"),
                         );
                         res
                     }))?;
-                u16::write::<T>(&baz, buf, ())
+                <u16>::write::<T>(&baz, buf, ())
                     .with_context(|| ::alloc::__export::must_use({
                         let res = ::alloc::fmt::format(
                             format_args!("Writing field \'{0}\'", "baz"),
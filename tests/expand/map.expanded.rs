@@ -10,23 +10,23 @@ impl<B: BitBuf> ::parsely_rs::ParselyRead<B> for Foo {
         buf: &mut B,
         (): (),
     ) -> ::parsely_rs::ParselyResult<Self> {
-        let value = {
-            let original_value = ::parsely_rs::ParselyRead::read::<T>(buf, ())
-                .with_context(|| ::alloc::__export::must_use({
-                    let res = ::alloc::fmt::format(
-                        format_args!("Reading raw value for field \'{0}\'", "value"),
-                    );
-                    res
-                }))?;
-            (|v: u8| { v.to_string() })(original_value)
-                .into_parsely_result()
-                .with_context(|| ::alloc::__export::must_use({
-                    let res = ::alloc::fmt::format(
-                        format_args!("Mapping raw value for field \'{0}\'", "value"),
-                    );
-                    res
-                }))
-        }
+        let value = ::parsely_rs::ParselyRead::read::<T>(buf, ())
+            .with_context(|| ::alloc::__export::must_use({
+                let res = ::alloc::fmt::format(
+                    format_args!("Reading raw value for field \'{0}\'", "value"),
+                );
+                res
+            }))
+            .and_then(|original_value| {
+                (|v: u8| { v.to_string() })(original_value)
+                    .into_parsely_result()
+                    .with_context(|| ::alloc::__export::must_use({
+                        let res = ::alloc::fmt::format(
+                            format_args!("Mapping raw value for field \'{0}\'", "value"),
+                        );
+                        res
+                    }))
+            })
             .with_context(|| "Reading field 'value'")?;
         Ok(Self { value })
     }
@@ -36,7 +36,7 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
     fn write<T: ByteOrder>(&self, buf: &mut B, (): Self::Ctx) -> ParselyResult<()> {
         {
             let mapped_value = (|v: &str| { v.parse::<u8>() })(&self.value);
-            let result = <_ as IntoWritableParselyResult<
+            let mapped_value = <_ as IntoWritableParselyResult<
                 _,
                 B,
             >>::into_writable_parsely_result(mapped_value)
@@ -46,7 +46,7 @@ impl<B: BitBufMut> ::parsely_rs::ParselyWrite<B> for Foo {
                     );
                     res
                 }))?;
-            ::parsely_rs::ParselyWrite::write::<T>(&result, buf, ())
+            ::parsely_rs::ParselyWrite::write::<T>(&mapped_value, buf, ())
                 .with_context(|| ::alloc::__export::must_use({
                     let res = ::alloc::fmt::format(
                         format_args!("Writing mapped value for field \'{0}\'", "value"),
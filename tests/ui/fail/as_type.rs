@@ -0,0 +1,10 @@
+use parsely_rs::*;
+
+// The attribute is spelled 'as': 'as_type' is only the name it's parsed as
+#[derive(ParselyRead)]
+struct Foo {
+    #[parsely(as_type = "u8")]
+    value: u32,
+}
+
+fn main() {}
@@ -6,6 +6,54 @@ struct Foo {
     one: u8,
 }
 
+// Bit-level alignment: skip to the next byte after a bitfield
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Flags {
+    #[parsely(alignment(bits = 8))]
+    flags: u3,
+    value: u8,
+}
+
+// A pad value which starts part of the way through a byte: the padding takes the pad value's bits
+// at the same positions
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct PaddedFlags {
+    #[parsely(alignment(bits = 8, pad = 0b1010_0101))]
+    flags: u3,
+    #[parsely(alignment(bits = 16, pad = 0b1010_0101))]
+    value: u8,
+}
+
+// Alignment relative to the start of the struct, rather than the start of the field, and with a
+// non-zero pad value
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct MessageAligned {
+    one: u8,
+    #[parsely(alignment(bytes = 4, relative_to_message, pad = 0xff))]
+    two: u8,
+    three: u8,
+}
+
+// Alignment relative to an explicit base: `offset` is the number of bits which came before the
+// struct
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(
+    required_context("offset: usize"),
+    alignment(bytes = 4, offset = "offset")
+)]
+struct Nested {
+    one: u8,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8", alignment = 4)]
+enum Aligned {
+    #[parsely(id = 1)]
+    One(u8),
+    #[parsely(id = 2, alignment(bytes = 4, relative_to_message))]
+    Two(u8),
+}
+
 fn main() {
     let mut bits = Bits::from_static_bytes(&[42, 0, 0, 0]);
 
@@ -17,4 +65,65 @@ fn main() {
 
     Foo::write::<NetworkOrder>(&foo, &mut bits_mut, ()).unwrap();
     assert_eq!(bits_mut.len_bytes(), 4);
+
+    let data: &[u8] = &[0b101_00000, 42];
+    let mut bits = Bits::from_static_bytes(data);
+    let flags = Flags::read::<NetworkOrder>(&mut bits, ()).unwrap();
+    assert_eq!(
+        flags,
+        Flags {
+            flags: u3::new(5),
+            value: 42
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    flags.write::<NetworkOrder>(&mut bits_mut, ()).unwrap();
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let value = PaddedFlags {
+        flags: u3::new(0),
+        value: 0,
+    };
+    let mut bits_mut = BitsMut::new();
+    value.write::<NetworkOrder>(&mut bits_mut, ()).unwrap();
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[0b000_00101, 0, 0b1010_0101])
+    );
+
+    let data: &[u8] = &[1, 2, 0xff, 0xff, 3];
+    let mut bits = Bits::from_static_bytes(data);
+    let value = MessageAligned::read::<NetworkOrder>(&mut bits, ()).unwrap();
+    assert_eq!(
+        value,
+        MessageAligned {
+            one: 1,
+            two: 2,
+            three: 3
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    value.write::<NetworkOrder>(&mut bits_mut, ()).unwrap();
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[1, 0, 0, 2]);
+    let nested = Nested::read::<NetworkOrder>(&mut bits, (8,)).unwrap();
+    assert_eq!(nested.one, 1);
+    assert_eq!(bits.remaining_bytes(), 1);
+    let mut bits_mut = BitsMut::new();
+    nested.write::<NetworkOrder>(&mut bits_mut, (8,)).unwrap();
+    assert_eq!(bits_mut.len_bytes(), 3);
+
+    let data: &[u8] = &[1, 42, 0, 0];
+    let mut bits = Bits::from_static_bytes(data);
+    let value = Aligned::read::<NetworkOrder>(&mut bits, ()).unwrap();
+    assert_eq!(value, Aligned::One(42));
+    let mut bits_mut = BitsMut::new();
+    value.write::<NetworkOrder>(&mut bits_mut, ()).unwrap();
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[2, 42, 0, 0]);
+    let value = Aligned::read::<NetworkOrder>(&mut bits, ()).unwrap();
+    assert_eq!(value, Aligned::Two(42));
+    assert_eq!(bits.remaining_bytes(), 0);
 }
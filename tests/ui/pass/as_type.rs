@@ -0,0 +1,97 @@
+use parsely_rs::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+impl TryFrom<u8> for Color {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Color::Red),
+            1 => Ok(Color::Green),
+            2 => Ok(Color::Blue),
+            v => Err(format!("Invalid color: {v}")),
+        }
+    }
+}
+
+impl From<Color> for u8 {
+    fn from(value: Color) -> Self {
+        value as u8
+    }
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Foo {
+    #[parsely(as = "u8")]
+    color: Color,
+    // A u32 field that's only 16 bits wide in the buffer
+    #[parsely(as = "u16")]
+    length: u32,
+    has_more: bool,
+    reserved: u7,
+    #[parsely(as = "u8")]
+    #[parsely_read(when = "has_more")]
+    more: Option<Color>,
+    #[parsely(as = "u8")]
+    #[parsely_read(count = "2")]
+    others: Vec<Color>,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+enum Bar {
+    #[parsely(id = 1)]
+    One(#[parsely(as = "u8")] Color),
+}
+
+fn main() {
+    let data: &[u8] = &[1, 0, 42, 0b1000_0000, 0, 2, 0];
+    let mut bits = Bits::from_static_bytes(data);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(
+        foo,
+        Foo {
+            color: Color::Green,
+            length: 42,
+            has_more: true,
+            reserved: u7::new(0),
+            more: Some(Color::Red),
+            others: vec![Color::Blue, Color::Red],
+        }
+    );
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Conversion errors on read...
+    let mut bits = Bits::from_static_bytes(&[3, 0, 42, 0, 0, 0]);
+    assert!(Foo::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    // ...and on write
+    let foo = Foo {
+        color: Color::Green,
+        length: 1 << 16,
+        has_more: false,
+        reserved: u7::new(0),
+        more: None,
+        others: vec![Color::Blue, Color::Red],
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(foo.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+
+    let mut bits = Bits::from_static_bytes(&[1, 2]);
+    let bar = Bar::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(bar, Bar::One(Color::Blue));
+    let mut bits_mut = BitsMut::new();
+    bar.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&[1, 2]));
+}
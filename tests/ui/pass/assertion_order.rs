@@ -0,0 +1,49 @@
+use parsely_rs::*;
+
+#[derive(Debug, ParselyRead, ParselyWrite)]
+struct Foo {
+    // The assertion is declared first so it sees the raw value from the buffer
+    #[parsely_read(assertion = "|v: &u8| *v < 10", map = "|v: u8| v * 10")]
+    // The assertion is declared after the map so it sees the value that will be written
+    #[parsely_write(map = "|v: &u8| v / 10", assertion = "|v: &u8| *v < 10")]
+    tens: u8,
+    // Multiple maps and assertions are applied in order
+    #[parsely_read(
+        map = "|v: u8| v as u16 + 1",
+        assertion = "|v: &u16| *v > 1",
+        map = "|v: u16| v.to_string()",
+        assertion = "|v: &String| v.len() == 2"
+    )]
+    #[parsely_write(
+        assertion = "|v: &String| v.len() == 2",
+        map = "|v: &str| v.parse::<u16>()",
+        map = "|v: &u16| u8::try_from(*v - 1)"
+    )]
+    value: String,
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[4, 41]);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(foo.tens, 40);
+    assert_eq!(foo.value, "42");
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&[4, 41]));
+
+    // The raw value fails the assertion, even though the mapped one would pass it
+    let mut bits = Bits::from_static_bytes(&[10, 41]);
+    assert!(Foo::read::<NetworkOrder>(&mut bits, ()).is_err());
+    // The mapped value fails the assertion, even though the field's value would pass it
+    let mut bits = Bits::from_static_bytes(&[4, 0]);
+    assert!(Foo::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let foo = Foo {
+        tens: 100,
+        value: String::from("42"),
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(foo.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+}
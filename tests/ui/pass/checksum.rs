@@ -0,0 +1,125 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct PngChunk {
+    #[parsely_write(sync_expr = "self.data.len() as u32")]
+    length: u32,
+    chunk_type: u32,
+    #[parsely_read(count = "length")]
+    data: Vec<u8>,
+    #[parsely(checksum = "crc32", over = "chunk_type..this")]
+    crc: u32,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Ipv4Header {
+    version: u4,
+    header_length: u4,
+    dscp_ecn: u8,
+    total_length: u16,
+    identification: u16,
+    flags_fragment_offset: u16,
+    ttl: u8,
+    protocol: u8,
+    #[parsely(checksum = "internet", over = "start..end")]
+    checksum: u16,
+    source: u32,
+    destination: u32,
+}
+
+/// A custom algorithm: the xor of all the bytes
+struct Xor;
+
+impl Checksum for Xor {
+    fn checksum(data: &[u8]) -> u64 {
+        data.iter().fold(0, |acc, byte| acc ^ *byte) as u64
+    }
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Frame {
+    #[parsely(checksum = "Xor", over = "first..=second")]
+    check: u8,
+    first: u8,
+    second: u8,
+    trailer: u8,
+}
+
+fn main() {
+    // The built-in algorithms give the standard check values
+    assert_eq!(checksum::Crc8::checksum(b"123456789"), 0xf4);
+    assert_eq!(checksum::Crc16::checksum(b"123456789"), 0xbb3d);
+    assert_eq!(checksum::Crc16Ccitt::checksum(b"123456789"), 0x29b1);
+    assert_eq!(checksum::Crc16Kermit::checksum(b"123456789"), 0x2189);
+    assert_eq!(checksum::Crc32::checksum(b"123456789"), 0xcbf43926);
+    assert_eq!(checksum::Crc32c::checksum(b"123456789"), 0xe3069283);
+    assert_eq!(checksum::Adler32::checksum(b"Wikipedia"), 0x11e60398);
+    assert_eq!(checksum::Fletcher16::checksum(b"abcde"), 0xc8f0);
+    assert_eq!(checksum::Fletcher32::checksum(b"abcde"), 0xf04fc729);
+
+    // A PNG IEND chunk
+    let data: &[u8] = &[0, 0, 0, 0, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82];
+    let mut bits = Bits::from_static_bytes(data);
+    let chunk = PngChunk::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(chunk.chunk_type, 0x49454e44);
+    assert_eq!(chunk.crc, 0xae426082);
+
+    // The checksum is computed when writing, whatever the field holds
+    let mut bits_mut = BitsMut::new();
+    PngChunk { crc: 0, ..chunk }
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // A checksum mismatch is reported with a ChecksumError
+    let mut bits =
+        Bits::from_static_bytes(&[0, 0, 0, 0, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x83]);
+    let error = PngChunk::read::<NetworkOrder>(&mut bits, ()).expect_err("checksum mismatch");
+    let checksum_error = error
+        .downcast_ref::<ChecksumError>()
+        .expect("a checksum error");
+    assert_eq!(
+        checksum_error,
+        &ChecksumError {
+            field: "crc".to_owned(),
+            expected: 0xae426082,
+            found: 0xae426083,
+        }
+    );
+
+    // The checksum field is treated as zero when it's in the checksum's range
+    let data: &[u8] = &[
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let header = Ipv4Header::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(header.checksum, 0xb861);
+    let mut bits_mut = BitsMut::new();
+    Ipv4Header {
+        checksum: 0xffff,
+        ..header
+    }
+    .write::<NetworkOrder>(&mut bits_mut, ())
+    .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // A custom algorithm, over fields which come after the checksum
+    let frame = Frame {
+        check: 0,
+        first: 0b1100,
+        second: 0b1010,
+        trailer: 0xff,
+    };
+    let mut bits_mut = BitsMut::new();
+    frame
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    let mut bits = bits_mut.freeze();
+    assert_eq!(
+        bits,
+        Bits::from_static_bytes(&[0b0110, 0b1100, 0b1010, 0xff])
+    );
+    let frame = Frame::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(frame.check, 0b0110);
+}
@@ -0,0 +1,71 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(required_context("index: usize"))]
+struct Report {
+    // Only the first report has an id
+    #[parsely_read(when = "index == 0")]
+    id: Option<u8>,
+    value: u8,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Foo {
+    // Each report is given its index
+    #[parsely_read(elem_context("idx"))]
+    #[parsely_read(count = "2")]
+    reports: Vec<Report>,
+    // Each value must be larger than the one before it
+    #[parsely(elem_assertion = "|v: &u8| items.last().is_none_or(|last| v > last)")]
+    #[parsely_read(count = "3")]
+    increasing: Vec<u8>,
+    // Each element is mapped individually, and can be asserted on before or after mapping
+    #[parsely_read(
+        count = "2",
+        elem_assertion = "|v: &u8| *v < 100",
+        elem_map = "|v: u8| v.to_string()",
+        elem_assertion = "|v: &String| !v.is_empty()"
+    )]
+    #[parsely_write(elem_map = "|v: &str| v.parse::<u8>()")]
+    strings: Vec<String>,
+}
+
+fn main() {
+    let data: &[u8] = &[42, 1, 2, 1, 2, 3, 7, 99];
+    let mut bits = Bits::from_static_bytes(data);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(
+        foo,
+        Foo {
+            reports: vec![
+                Report {
+                    id: Some(42),
+                    value: 1
+                },
+                Report { id: None, value: 2 }
+            ],
+            increasing: vec![1, 2, 3],
+            strings: vec!["7".to_owned(), "99".to_owned()],
+        }
+    );
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // A value that isn't larger than the previous one fails the element assertion
+    let mut bits = Bits::from_static_bytes(&[42, 1, 2, 1, 3, 3, 7, 99]);
+    assert!(Foo::read::<NetworkOrder>(&mut bits, ()).is_err());
+    // As does a raw value that's too large
+    let mut bits = Bits::from_static_bytes(&[42, 1, 2, 1, 2, 3, 7, 100]);
+    assert!(Foo::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    // The assertion is checked on write, too
+    let foo = Foo {
+        increasing: vec![3, 2, 1],
+        ..foo
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(foo.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+}
@@ -0,0 +1,116 @@
+use parsely_rs::*;
+
+const CONTROL_BASE: u8 = 0x20;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+#[repr(u8)]
+enum MessageType {
+    // Variants without an explicit discriminant follow on from the previous one, as in Rust
+    Data,
+    Ack,
+    Ping = 0x10,
+    Pong,
+    Close = CONTROL_BASE,
+    // An explicit id takes precedence over the discriminant
+    #[parsely(id = 0x7f)]
+    Reset = 0x30,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+#[repr(u8)]
+enum Codepoint {
+    Start = 1,
+    Stop = 2,
+    // Any other value is read into (and written from) this variant
+    #[parsely(unknown)]
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+#[repr(u8)]
+enum Command {
+    #[parsely(id = 0x40)]
+    Get,
+    // Implicit ids follow on from the previous variant's id
+    Set(u8),
+    #[parsely(unknown)]
+    Other(u8),
+    Clear,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+#[repr(u8)]
+enum Flag {
+    Off = 0,
+    On = 1,
+    #[parsely(unknown)]
+    Invalid = 0xff,
+}
+
+fn main() {
+    let data: &[u8] = &[0, 1, 0x10, 0x11, 0x20, 0x7f];
+    let mut bits = Bits::from_static_bytes(data);
+    let mut bits_mut = BitsMut::new();
+    for expected in [
+        MessageType::Data,
+        MessageType::Ack,
+        MessageType::Ping,
+        MessageType::Pong,
+        MessageType::Close,
+        MessageType::Reset,
+    ] {
+        let message_type =
+            MessageType::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+        assert_eq!(message_type, expected);
+        message_type
+            .write::<NetworkOrder>(&mut bits_mut, ())
+            .expect("successful write");
+    }
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[0x30]);
+    assert!(MessageType::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[2, 42];
+    let mut bits = Bits::from_static_bytes(data);
+    let stop = Codepoint::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(stop, Codepoint::Stop);
+    let other = Codepoint::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(other, Codepoint::Other(42));
+    let mut bits_mut = BitsMut::new();
+    stop.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    other
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let data: &[u8] = &[0x40, 0x41, 7, 0x43, 0x42];
+    let mut bits = Bits::from_static_bytes(data);
+    let mut bits_mut = BitsMut::new();
+    for expected in [
+        Command::Get,
+        Command::Set(7),
+        Command::Clear,
+        Command::Other(0x42),
+    ] {
+        let command = Command::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+        assert_eq!(command, expected);
+        command
+            .write::<NetworkOrder>(&mut bits_mut, ())
+            .expect("successful write");
+    }
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[7]);
+    let flag = Flag::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(flag, Flag::Invalid);
+    let mut bits_mut = BitsMut::new();
+    flag.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&[0xff]));
+}
@@ -0,0 +1,99 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Header {
+    packet_type: u8,
+    length: u8,
+}
+
+// The packet type has already been read as part of the header, so it's passed in as context and
+// not read from the buffer.  Writing the payload doesn't write the packet type either.
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(required_context("packet_type: u8"))]
+#[parsely(key = "packet_type")]
+enum Payload {
+    #[parsely(id = 200)]
+    SenderReport { ssrc: u32 },
+    #[parsely(id = 203)]
+    Bye,
+    // The key is available to the variant's context as 'key' (and is the variant's id when
+    // writing)
+    #[parsely(id = 204, required_context("packet_type: u8"), context("*key"))]
+    App {
+        #[parsely_read(count = "usize::from(packet_type - 200)")]
+        data: Vec<u8>,
+    },
+}
+
+// The key can be an arbitrary expression which can return a result.  Here the tag is still
+// written along with the payload.
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(
+    required_context("packet_type: u8"),
+    key = "packet_type.checked_sub(200).ok_or(anyhow!(\"Invalid packet type\"))"
+)]
+#[parsely_write(key_type = "u8")]
+enum OffsetPayload {
+    #[parsely(id = 0)]
+    SenderReport(u32),
+    #[parsely(id = 3)]
+    Bye,
+}
+
+fn main() {
+    let data: &[u8] = &[200, 4, 0, 0, 0, 42];
+    let mut bits = Bits::from_static_bytes(data);
+    let header = Header::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    let payload =
+        Payload::read::<NetworkOrder>(&mut bits, (header.packet_type,)).expect("successful parse");
+    assert_eq!(payload, Payload::SenderReport { ssrc: 42 });
+    assert_eq!(bits.remaining_bytes(), 0);
+
+    let mut bits_mut = BitsMut::new();
+    header
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    payload
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[]);
+    let header = Header {
+        packet_type: 203,
+        length: 0,
+    };
+    let payload =
+        Payload::read::<NetworkOrder>(&mut bits, (header.packet_type,)).expect("successful parse");
+    assert_eq!(payload, Payload::Bye);
+    let payload = OffsetPayload::read::<NetworkOrder>(&mut bits, (header.packet_type,))
+        .expect("successful parse");
+    assert_eq!(payload, OffsetPayload::Bye);
+
+    let mut bits_mut = BitsMut::new();
+    payload
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&[3]));
+
+    let data: &[u8] = &[1, 2, 3, 4];
+    let mut bits = Bits::from_static_bytes(data);
+    let payload = Payload::read::<NetworkOrder>(&mut bits, (204,)).expect("successful parse");
+    assert_eq!(
+        payload,
+        Payload::App {
+            data: vec![1, 2, 3, 4]
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    payload
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // The key doesn't match any variant
+    let mut bits = Bits::from_static_bytes(&[0, 0, 0, 42]);
+    assert!(Payload::read::<NetworkOrder>(&mut bits, (201,)).is_err());
+    // Or can't be evaluated
+    assert!(OffsetPayload::read::<NetworkOrder>(&mut bits, (100,)).is_err());
+}
@@ -0,0 +1,59 @@
+use parsely_rs::*;
+
+// Tuple key elements are matched as patterns, so values of non-standard width types need to be
+// given as constants
+const MANAGEMENT: u2 = u2::new(0);
+const DATA: u2 = u2::new(2);
+const BEACON: u6 = u6::new(8);
+const QOS_DATA: u6 = u6::new(8);
+
+// A frame selected by its (type, subtype) pair
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "(u2, u6)")]
+enum Frame {
+    #[parsely(id = "(MANAGEMENT, BEACON)")]
+    Beacon { interval: u16 },
+    #[parsely(id = "(DATA, QOS_DATA)")]
+    QosData { tid: u8 },
+    #[parsely(unknown)]
+    Other((u2, u6)),
+}
+
+// The key can also be a tuple of expressions
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(required_context("class: u8", "method: u16"))]
+#[parsely(key = "(class, method)")]
+enum StunMessage {
+    #[parsely(id = "(0b00, 0x001)")]
+    BindingRequest,
+    #[parsely(id = "(0b10, 0x001)")]
+    BindingResponse { address: u32 },
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        0b00_001000, 0, 100,
+        0b10_001000, 5,
+        0b01_000011,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let mut bits_mut = BitsMut::new();
+    for expected in [
+        Frame::Beacon { interval: 100 },
+        Frame::QosData { tid: 5 },
+        Frame::Other((u2::new(1), u6::new(3))),
+    ] {
+        let frame = Frame::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+        assert_eq!(frame, expected);
+        frame
+            .write::<NetworkOrder>(&mut bits_mut, ())
+            .expect("successful write");
+    }
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[0, 0, 0, 42]);
+    let message =
+        StunMessage::read::<NetworkOrder>(&mut bits, (0b10, 0x001)).expect("successful read");
+    assert_eq!(message, StunMessage::BindingResponse { address: 42 });
+}
@@ -0,0 +1,72 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Foo {
+    // Q8.8
+    gain: IFixed<8, 8>,
+    // UQ16.16
+    ratio: UFixed<16, 16>,
+    // An odd width: 3 integer bits, 2 fractional bits
+    small: UFixed<3, 2>,
+    // Signed 1/256 dB, 11 bits total
+    level: IFixed<3, 8>,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // -1.5
+        0xFE, 0x80,
+        // 2.25
+        0x00, 0x02, 0x40, 0x00,
+        // 0b110_01 (6.25), followed by 0b111_1111_1111 (-1/256 dB)
+        0b11001_111, 0b11111111,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(foo.gain.to_f64(), -1.5);
+    assert_eq!(f64::from(foo.ratio), 2.25);
+    assert_eq!(foo.small.to_f64(), 6.25);
+    assert_eq!(foo.level.raw(), -1);
+    assert_eq!(foo.level.to_f64(), -1.0 / 256.0);
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let foo = Foo {
+        gain: IFixed::try_from(-1.5).unwrap(),
+        ratio: UFixed::try_from(2.25).unwrap(),
+        small: UFixed::try_from(6.25).unwrap(),
+        level: IFixed::from_raw(-1),
+    };
+    let mut bits = Bits::from_static_bytes(data);
+    assert_eq!(foo, Foo::read::<NetworkOrder>(&mut bits, ()).unwrap());
+
+    // Exact conversions fail for values that can't be represented
+    assert!(UFixed::<3, 2>::try_from(0.1).is_err());
+    assert_eq!(
+        UFixed::<3, 2>::from_f64_rounded(0.3).unwrap().to_f64(),
+        0.25
+    );
+    // Out of range values fail
+    assert!(UFixed::<3, 2>::try_from(8.0).is_err());
+    assert!(UFixed::<3, 2>::try_from(-0.25).is_err());
+    assert!(IFixed::<8, 8>::try_from(128.0).is_err());
+    assert_eq!(IFixed::<8, 8>::try_from(-128.0).unwrap().raw(), -32768);
+    // 64 bit values are limited by 2^64, which (unlike MAX_RAW) is exact as an f64
+    assert!(UFixed::<64, 0>::try_from(2f64.powi(64)).is_err());
+    assert!(UFixed::<32, 32>::from_f64_rounded(2f64.powi(32)).is_err());
+    assert_eq!(
+        UFixed::<64, 0>::try_from(2f64.powi(64) - 2048.0)
+            .unwrap()
+            .raw(),
+        u64::MAX - 2047
+    );
+    assert!(IFixed::<64, 0>::try_from(2f64.powi(63)).is_err());
+    assert_eq!(
+        IFixed::<64, 0>::try_from(-(2f64.powi(63))).unwrap().raw(),
+        i64::MIN
+    );
+}
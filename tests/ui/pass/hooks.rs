@@ -0,0 +1,77 @@
+use parsely_rs::*;
+
+fn skip_vendor_byte<B: BitBuf>(buf: &mut B) -> ParselyResult<()> {
+    if buf.get_u8()? != 0xee {
+        bail!("Missing vendor byte");
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(
+    required_context("key: u8"),
+    pre_read = "skip_vendor_byte(buf)",
+    post_read = "value.payload.iter_mut().for_each(|b| *b ^= key)"
+)]
+#[parsely_write(
+    required_context("key: u8"),
+    pre_write = "buf.put_u8(0xee)",
+    post_write = "buf.put_u8(key)"
+)]
+struct Packet {
+    #[parsely_read(post_read = "check_length(length)")]
+    length: u8,
+    // The field can be modified by its hook
+    #[parsely_read(count = "length", post_read = "payload.reverse()")]
+    payload: Vec<u8>,
+}
+
+fn check_length(length: u8) -> ParselyResult<()> {
+    if length > 4 {
+        bail!("Length {length} is too long");
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Offsets {
+    first: u8,
+    // Hooks can inspect the buffer and the other fields
+    #[parsely_read(pre_read = "assert_eq!(buf.remaining_bytes(), 1)")]
+    #[parsely_write(pre_write = "assert_eq!(self.first, 1)")]
+    second: u8,
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[0xee, 2, 0x0f ^ 0x55, 0xf0 ^ 0x55]);
+    let packet = Packet::read::<NetworkOrder>(&mut bits, (0x55,)).expect("successful read");
+    // Reversed by the field's hook and decrypted by the struct's
+    assert_eq!(packet.payload, vec![0xf0, 0x0f]);
+
+    let mut bits = Bits::from_static_bytes(&[0xaa, 0]);
+    let Err(error) = Packet::read::<NetworkOrder>(&mut bits, (0x55,)) else {
+        panic!("read should fail");
+    };
+    assert_eq!(error.to_string(), "pre_read hook for 'Packet'");
+    let mut bits = Bits::from_static_bytes(&[0xee, 5, 0, 0, 0, 0, 0]);
+    let Err(error) = Packet::read::<NetworkOrder>(&mut bits, (0x55,)) else {
+        panic!("read should fail");
+    };
+    assert_eq!(error.to_string(), "post_read hook for field 'length'");
+
+    let mut bits_mut = BitsMut::new();
+    packet
+        .write::<NetworkOrder>(&mut bits_mut, (0x55,))
+        .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[0xee, 2, 0xf0, 0x0f, 0x55])
+    );
+
+    let mut bits = Bits::from_static_bytes(&[1, 2]);
+    let offsets = Offsets::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    let mut bits_mut = BitsMut::new();
+    offsets
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+}
@@ -0,0 +1,73 @@
+use parsely_rs::*;
+
+/// A tag which is 4 bits wide in version 1 of the protocol and 8 bits wide after that
+#[derive(Debug, PartialEq)]
+struct Tag(u8);
+
+impl<B: BitBuf> ParselyRead<B> for Tag {
+    type Ctx = (u8,);
+
+    fn read<T: ByteOrder>(buf: &mut B, (version,): Self::Ctx) -> ParselyResult<Self> {
+        if version == 1 {
+            Ok(Tag(u8::from(u4::read::<T>(buf, ())?)))
+        } else {
+            Ok(Tag(buf.get_u8()?))
+        }
+    }
+}
+
+impl<B: BitBufMut> ParselyWrite<B> for Tag {
+    type Ctx = (u8,);
+
+    fn write<T: ByteOrder>(&self, buf: &mut B, (version,): Self::Ctx) -> ParselyResult<()> {
+        if version == 1 {
+            u4::new(self.0).write::<T>(buf, ())
+        } else {
+            self.0.write::<T>(buf, ())
+        }
+    }
+}
+
+impl_stateless_sync!(Tag);
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(
+    key_type = "Tag",
+    key_context("version"),
+    required_context("version: u8")
+)]
+enum Message {
+    #[parsely(id = "Tag(1)")]
+    One(u8),
+    #[parsely(id = "Tag(2)")]
+    Two {
+        // The number is held as a NUL-terminated string in the buffer
+        #[parsely(context("StringOptions::nul_terminated()"))]
+        #[parsely_read(map = "|v: String| v.parse::<u32>()")]
+        #[parsely_write(map = "|v: &u32| v.to_string()")]
+        number: u32,
+    },
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[0x10, 0x20]);
+    let message = Message::read::<NetworkOrder>(&mut bits, (1,)).expect("successful parse");
+    assert_eq!(message, Message::One(2));
+    let mut bits_mut = BitsMut::new();
+    message
+        .write::<NetworkOrder>(&mut bits_mut, (1,))
+        .expect("successful write");
+    let mut bits = bits_mut.freeze();
+    assert_eq!(bits.get_u4().unwrap(), 1);
+    assert_eq!(bits.get_u8().unwrap(), 2);
+
+    let data: &[u8] = &[2, b'4', b'2', 0];
+    let mut bits = Bits::from_static_bytes(data);
+    let message = Message::read::<NetworkOrder>(&mut bits, (2,)).expect("successful parse");
+    assert_eq!(message, Message::Two { number: 42 });
+    let mut bits_mut = BitsMut::new();
+    message
+        .write::<NetworkOrder>(&mut bits_mut, (2,))
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+}
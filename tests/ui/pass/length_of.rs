@@ -0,0 +1,85 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Tlv {
+    tag: u8,
+    #[parsely_write(length_of = "value")]
+    length: u8,
+    #[parsely_read(count = "length")]
+    value: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(required_context("length: u16"))]
+struct Block {
+    #[parsely_read(count = "(length + 1) / 2")]
+    tlvs: Vec<Tlv>,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Message {
+    version: u8,
+    #[parsely_write(length_of = "block", units = 4, adjust = -1)]
+    length: u16,
+    #[parsely_read(context("length"))]
+    block: Block,
+}
+
+fn main() {
+    // The length is filled in from what was written, whatever the field holds
+    let tlv = Tlv {
+        tag: 1,
+        length: 0,
+        value: vec![1, 2, 3],
+    };
+    let mut bits_mut = BitsMut::new();
+    tlv.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    let mut bits = bits_mut.freeze();
+    assert_eq!(bits, Bits::from_static_bytes(&[1, 3, 1, 2, 3]));
+    let tlv = Tlv::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(tlv.length, 3);
+
+    // Nested variable-length payloads, with the length in 4 byte units minus one
+    let message = Message {
+        version: 2,
+        length: 0,
+        block: Block {
+            tlvs: vec![
+                Tlv {
+                    tag: 1,
+                    length: 0,
+                    value: vec![1, 2],
+                },
+                Tlv {
+                    tag: 2,
+                    length: 0,
+                    value: vec![3, 4],
+                },
+            ],
+        },
+    };
+    let mut bits_mut = BitsMut::new();
+    message
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[2, 0, 1, 1, 2, 1, 2, 2, 2, 3, 4])
+    );
+
+    // The written length must be a whole number of units
+    let message = Message {
+        version: 2,
+        length: 0,
+        block: Block {
+            tlvs: vec![Tlv {
+                tag: 1,
+                length: 0,
+                value: vec![1],
+            }],
+        },
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(message.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+}
@@ -0,0 +1,118 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(magic = b"\x89PNG")]
+struct PngSignature {
+    line_ending: u32,
+}
+
+// The RTCP version is always 2, so it doesn't need to be stored in the struct
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtcpHeader {
+    #[parsely(const_field(u2 = 2))]
+    has_padding: bool,
+    report_count: u5,
+    packet_type: u8,
+    // Multiple constants are handled in the order they're declared
+    #[parsely(const_field(u4 = 0xf, u4 = 0))]
+    length_field: u16,
+}
+
+// Constants after the last field are given on the struct
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(trailing_const_field(u4 = 0, u8 = 0xff))]
+struct Record {
+    value: u12,
+}
+
+// A struct with no fields can be made up of constants
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(trailing_const_field(u16 = 0xaa55))]
+struct SyncWord;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(magic = b"MSG", key_type = "u8")]
+enum Message {
+    #[parsely(id = 1)]
+    Hello,
+}
+
+fn main() {
+    let data: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    let mut bits = Bits::from_static_bytes(data);
+    let signature = PngSignature::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(signature.line_ending, 0x0d0a1a0a);
+    let mut bits_mut = BitsMut::new();
+    signature
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let mut bits = Bits::from_static_bytes(&[0x89, b'G', b'I', b'F', 0, 0, 0, 0]);
+    assert!(PngSignature::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[0b10_1_00001, 200, 0xf0, 0x00, 0x01];
+    let mut bits = Bits::from_static_bytes(&data[..]);
+    let header = RtcpHeader::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        header,
+        RtcpHeader {
+            has_padding: true,
+            report_count: u5::new(1),
+            packet_type: 200,
+            length_field: 1,
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    header
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Wrong version
+    let mut bits = Bits::from_static_bytes(&[0b01_1_00001, 200, 0xf0, 0x00, 0x01]);
+    assert!(RtcpHeader::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[0x12, 0x30, 0xff];
+    let mut bits = Bits::from_static_bytes(data);
+    let record = Record::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        record,
+        Record {
+            value: u12::new(0x123)
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    record
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Wrong trailing constant
+    let mut bits = Bits::from_static_bytes(&[0x12, 0x30, 0xfe]);
+    let Err(e) = Record::read::<NetworkOrder>(&mut bits, ()) else {
+        panic!("expected an error");
+    };
+    assert!(format!("{e:?}").contains("constant after the fields of 'Record' (u8 = 0xff)"));
+
+    let data: &[u8] = &[0xaa, 0x55];
+    let mut bits = Bits::from_static_bytes(data);
+    let sync_word = SyncWord::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    let mut bits_mut = BitsMut::new();
+    sync_word
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+    let mut bits = Bits::from_static_bytes(&[0x55, 0xaa]);
+    assert!(SyncWord::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[b'M', b'S', b'G', 1];
+    let mut bits = Bits::from_static_bytes(data);
+    let message = Message::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(message, Message::Hello);
+    let mut bits_mut = BitsMut::new();
+    message
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+}
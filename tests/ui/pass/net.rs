@@ -0,0 +1,87 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Foo {
+    mac: MacAddr,
+    v4: Ipv4Addr,
+    v6: Ipv6Addr,
+    ip: IpAddr,
+    socket: SocketAddr,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(required_context("transaction_id: [u8; 12]"))]
+struct StunAddresses {
+    mapped: MappedAddress,
+    #[parsely(context("transaction_id"))]
+    xor_v4: XorMappedAddress,
+    #[parsely(context("transaction_id"))]
+    xor_v6: XorMappedAddress,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // mac
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55,
+        // v4
+        192, 168, 1, 1,
+        // v6
+        0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        // ip: family, address
+        0x01, 10, 0, 0, 1,
+        // socket: family, port, address
+        0x02, 0x0d, 0x96, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(foo.mac, MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55));
+    assert_eq!(foo.mac.to_string(), "00:11:22:33:44:55");
+    assert_eq!(foo.v4, Ipv4Addr::new(192, 168, 1, 1));
+    assert_eq!(foo.v6, "2001:db8::1".parse::<Ipv6Addr>().unwrap());
+    assert_eq!(foo.ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+    assert_eq!(foo.socket, "[::1]:3478".parse::<SocketAddr>().unwrap());
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // The XOR-MAPPED-ADDRESS values are from the RFC 5769 test vectors
+    let transaction_id = [
+        0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae,
+    ];
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // mapped: reserved, family, port, address
+        0x00, 0x01, 0x0d, 0x96, 192, 0, 2, 1,
+        // xor_v4
+        0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        // xor_v6
+        0x00, 0x02, 0xa1, 0x47, 0x01, 0x13, 0xa9, 0xfa, 0xa5, 0xd3, 0xf1, 0x79, 0xbc, 0x25, 0xf4,
+        0xb5, 0xbe, 0xd2, 0xb9, 0xd9,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let addresses = StunAddresses::read::<NetworkOrder>(&mut bits, (transaction_id,))
+        .expect("successful parse");
+    assert_eq!(addresses.mapped.0, "192.0.2.1:3478".parse().unwrap());
+    assert_eq!(addresses.xor_v4.0, "192.0.2.1:32853".parse().unwrap());
+    assert_eq!(
+        addresses.xor_v6.0,
+        "[2001:db8:1234:5678:11:2233:4455:6677]:32853"
+            .parse()
+            .unwrap()
+    );
+
+    let mut bits_mut = BitsMut::new();
+    addresses
+        .write::<NetworkOrder>(&mut bits_mut, (transaction_id,))
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Unknown address families are rejected
+    let mut bits = Bits::from_static_bytes(&[0x03, 1, 2, 3, 4]);
+    assert!(IpAddr::read::<NetworkOrder>(&mut bits, ()).is_err());
+}
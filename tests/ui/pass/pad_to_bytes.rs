@@ -0,0 +1,59 @@
+use parsely_rs::*;
+
+// A fixed-size record
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(pad_to_bytes = 8)]
+struct Record {
+    id: u16,
+    #[parsely(pad_to_bytes = 4)]
+    kind: u8,
+}
+
+// A size computed from a field: an RTCP packet is (length + 1) * 4 bytes long
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely_read(pad_to_bytes = "(usize::from(length_field) + 1) * 4")]
+#[parsely_write(pad_to_bytes = "(usize::from(self.length_field) + 1) * 4")]
+struct RtcpPacket {
+    packet_type: u8,
+    length_field: u16,
+    value: u16,
+}
+
+fn main() {
+    let data: &[u8] = &[0, 1, 7, 0, 0, 0, 0, 0];
+    let mut bits = Bits::from_static_bytes(data);
+    let record = Record::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(record, Record { id: 1, kind: 7 });
+    assert_eq!(bits.remaining_bits(), 0);
+    let mut bits_mut = BitsMut::new();
+    record
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Not enough data for the padding
+    let mut bits = Bits::from_static_bytes(&data[..6]);
+    assert!(Record::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[200, 0, 1, 0, 42, 0, 0, 0, 0xff];
+    let mut bits = Bits::from_static_bytes(data);
+    let packet = RtcpPacket::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(packet.value, 42);
+    assert_eq!(bits.remaining_bytes(), 1);
+    let mut bits_mut = BitsMut::new();
+    packet
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&data[..8]));
+
+    // The content is bigger than the size
+    let mut bits = Bits::from_static_bytes(&[200, 0, 0, 0, 42]);
+    assert!(RtcpPacket::read::<NetworkOrder>(&mut bits, ()).is_err());
+    let packet = RtcpPacket {
+        packet_type: 200,
+        length_field: 0,
+        value: 42,
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(packet.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+}
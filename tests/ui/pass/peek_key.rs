@@ -0,0 +1,109 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtcpHeader {
+    version: u2,
+    has_padding: bool,
+    report_count: u5,
+    packet_type: u8,
+    length_field: u16,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtcpBye {
+    header: RtcpHeader,
+    #[parsely_read(count = "u8::from(header.report_count)")]
+    ssrcs: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtcpUnknown {
+    header: RtcpHeader,
+    #[parsely_read(count = "usize::from(header.length_field) * 4")]
+    data: Vec<u8>,
+}
+
+// Each packet begins with its header, so the header is peeked to determine which packet to read
+// and then read again as part of the packet.
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "RtcpHeader", peek_key)]
+enum RtcpPacket {
+    #[parsely(id = "RtcpHeader { packet_type: 203, .. }")]
+    Bye(RtcpBye),
+    #[parsely(id = "_")]
+    Unknown(RtcpUnknown),
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Data {
+    kind: u8,
+    value: u16,
+}
+
+// A variant without fields doesn't read the tag again, so it's written separately
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8", peek_key)]
+enum Message {
+    #[parsely(id = 1)]
+    Ping,
+    #[parsely(id = 2)]
+    Data(Data),
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // Bye with 1 ssrc
+        0b10_0_00001, 203, 0, 1, 0, 0, 0, 42,
+        // Unknown packet type
+        0b10_0_00000, 210, 0, 1, 1, 2, 3, 4,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let bye = RtcpPacket::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    let RtcpPacket::Bye(ref packet) = bye else {
+        panic!("Expected a BYE packet, got {bye:?}");
+    };
+    assert_eq!(packet.header.packet_type, 203);
+    assert_eq!(packet.ssrcs, vec![42]);
+
+    let unknown = RtcpPacket::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    let RtcpPacket::Unknown(ref packet) = unknown else {
+        panic!("Expected an unknown packet, got {unknown:?}");
+    };
+    assert_eq!(packet.header.packet_type, 210);
+    assert_eq!(packet.data, vec![1, 2, 3, 4]);
+    assert_eq!(bits.remaining_bits(), 0);
+
+    // The header can be split across chunks of the buffer
+    let mut chained =
+        Bits::from_static_bytes(&data[..2]).chain(Bits::from_static_bytes(&data[2..]));
+    let packet = RtcpPacket::read::<NetworkOrder>(&mut chained, ()).expect("successful parse");
+    assert_eq!(packet, bye);
+    let packet = RtcpPacket::read::<NetworkOrder>(&mut chained, ()).expect("successful parse");
+    assert_eq!(packet, unknown);
+    assert_eq!(chained.remaining_bits(), 0);
+
+    // The tag isn't written separately, only as part of the packet
+    let mut bits_mut = BitsMut::new();
+    bye.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    unknown
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let data: &[u8] = &[1, 2, 0, 42];
+    let mut bits = Bits::from_static_bytes(data);
+    let ping = Message::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(ping, Message::Ping);
+    let message = Message::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(message, Message::Data(Data { kind: 2, value: 42 }));
+    assert_eq!(bits.remaining_bits(), 0);
+    let mut bits_mut = BitsMut::new();
+    ping.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    message
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+}
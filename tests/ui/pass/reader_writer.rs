@@ -0,0 +1,117 @@
+use parsely_rs::*;
+
+/// A LEB128-style variable length integer
+fn read_varint<B: BitBuf, T: ByteOrder>(buf: &mut B, _ctx: ()) -> ParselyResult<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = buf.get_u8().context("Reading varint byte")?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Varint is too long")
+}
+
+fn write_varint<B: BitBufMut, T: ByteOrder>(
+    value: &u64,
+    buf: &mut B,
+    _ctx: (),
+) -> ParselyResult<()> {
+    let mut value = *value;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.put_u8(byte)?;
+            return Ok(());
+        }
+        buf.put_u8(byte | 0x80)?;
+    }
+}
+
+/// An integer of the given number of bytes
+fn read_sized<B: BitBuf, T: ByteOrder>(buf: &mut B, (bytes,): (usize,)) -> ParselyResult<u32> {
+    let mut value = 0;
+    for _ in 0..bytes {
+        value = (value << 8) | buf.get_u8()? as u32;
+    }
+    Ok(value)
+}
+
+fn write_sized<B: BitBufMut, T: ByteOrder>(
+    value: &u32,
+    buf: &mut B,
+    (bytes,): (usize,),
+) -> ParselyResult<()> {
+    for i in (0..bytes).rev() {
+        buf.put_u8((value >> (i * 8)) as u8)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Message {
+    #[parsely_read(reader = "read_varint")]
+    #[parsely_write(writer = "write_varint")]
+    #[parsely(assertion = "|v: &u64| *v > 0", alignment = 2)]
+    id: u64,
+    has_extra: u8,
+    #[parsely_read(reader = "read_varint", when = "has_extra == 1")]
+    #[parsely_write(writer = "write_varint")]
+    extra: Option<u64>,
+    #[parsely_read(reader = "read_sized", count = "2")]
+    #[parsely_write(writer = "write_sized")]
+    #[parsely(elem_context("3"))]
+    values: Vec<u32>,
+}
+
+fn main() {
+    let data: &[u8] = &[
+        // id: 300, padded to 2 bytes
+        0xac, 0x02, //
+        // has_extra, then extra: 1
+        1, 0x01, //
+        // values
+        0, 0, 1, 0, 1, 0,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let message = Message::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        message,
+        Message {
+            id: 300,
+            has_extra: 1,
+            extra: Some(1),
+            values: vec![1, 256],
+        }
+    );
+
+    let mut bits_mut = BitsMut::new();
+    message
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Alignment padding is added after a short value
+    let mut bits_mut = BitsMut::new();
+    Message {
+        id: 1,
+        has_extra: 0,
+        extra: None,
+        values: vec![0, 0],
+    }
+    .write::<NetworkOrder>(&mut bits_mut, ())
+    .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[1, 0, 0, 0, 0, 0, 0, 0, 0])
+    );
+
+    // The assertion still applies, and errors are reported for the field
+    let mut bits = Bits::from_static_bytes(&[0, 0, 0]);
+    let Err(error) = Message::read::<NetworkOrder>(&mut bits, ()) else {
+        panic!("assertion should fail");
+    };
+    assert_eq!(error.to_string(), "Reading field 'id'");
+}
@@ -0,0 +1,92 @@
+use parsely_rs::*;
+
+// The 3 reserved bits between the data offset and the flags are discarded when reading and
+// written as zeros
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct TcpOffsetAndFlags {
+    data_offset: u4,
+    #[parsely(skip_bits = 3)]
+    ns: bool,
+    flags: u8,
+}
+
+// Reserved bits which must be zero when reading.  More than 64 bits can be skipped.
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct ZeroedReserved {
+    #[parsely(skip_bits(bits = 68, must_be_zero))]
+    value: u4,
+}
+
+// Reserved bits which are kept so they can be written back unchanged
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Preserved {
+    version: u4,
+    reserved: Reserved<4>,
+}
+
+// Reserved bits which are kept and must be zero
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct MustBeZero {
+    version: u4,
+    #[parsely(assertion = "|v: &Reserved<4>| v.is_zero()")]
+    reserved: Reserved<4>,
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[0b0101_111_1, 0x12]);
+    let value = TcpOffsetAndFlags::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        value,
+        TcpOffsetAndFlags {
+            data_offset: u4::new(5),
+            ns: true,
+            flags: 0x12,
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    value
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[0b0101_000_1, 0x12])
+    );
+
+    let data: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0x0a];
+    let mut bits = Bits::from_static_bytes(data);
+    let value = ZeroedReserved::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(value.value, u4::new(0xa));
+    let mut bits_mut = BitsMut::new();
+    value
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+    let mut bits = Bits::from_static_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 0x1a]);
+    assert!(ZeroedReserved::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    let data: &[u8] = &[0x2a];
+    let mut bits = Bits::from_static_bytes(data);
+    let value = Preserved::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(value.reserved.raw(), 0xa);
+    let mut bits_mut = BitsMut::new();
+    value
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    let value = Preserved {
+        version: u4::new(2),
+        reserved: Reserved::default(),
+    };
+    let mut bits_mut = BitsMut::new();
+    value
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&[0x20]));
+    assert!(Reserved::<4>::try_from_raw(0x10).is_err());
+
+    let mut bits = Bits::from_static_bytes(&[0x20]);
+    MustBeZero::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    let mut bits = Bits::from_static_bytes(&[0x21]);
+    assert!(MustBeZero::read::<NetworkOrder>(&mut bits, ()).is_err());
+}
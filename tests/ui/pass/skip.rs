@@ -0,0 +1,79 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct Sample {
+    #[parsely_write(sync_expr = "self.values.len() as u8")]
+    count: u8,
+    #[parsely_read(count = "count")]
+    values: Vec<u16>,
+    // Derived from fields read before it
+    #[parsely(skip, default = "values.iter().map(|v| *v as u32).sum()")]
+    total: u32,
+    #[parsely(skip, default)]
+    cached: Option<String>,
+    trailer: u8,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8")]
+enum Value {
+    #[parsely(id = 1)]
+    Number {
+        value: u8,
+        #[parsely(skip, default = "value % 2 == 0")]
+        even: bool,
+    },
+    #[parsely(id = 2)]
+    Tagged(u8, #[parsely(skip, default)] u32),
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[2, 0, 1, 0, 2, 0xff]);
+    let sample = Sample::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        sample,
+        Sample {
+            count: 2,
+            values: vec![1, 2],
+            total: 3,
+            cached: None,
+            trailer: 0xff,
+        }
+    );
+
+    // Skipped fields aren't written or synced, whatever they hold
+    let mut sample = Sample {
+        count: 0,
+        total: 100,
+        cached: Some("cached".to_owned()),
+        ..sample
+    };
+    sample.sync(()).expect("successful sync");
+    assert_eq!(sample.total, 100);
+    let mut bits_mut = BitsMut::new();
+    sample
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[2, 0, 1, 0, 2, 0xff])
+    );
+
+    let mut bits = Bits::from_static_bytes(&[1, 4]);
+    let value = Value::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        value,
+        Value::Number {
+            value: 4,
+            even: true
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    Value::Tagged(3, 7)
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    let mut bits = bits_mut.freeze();
+    assert_eq!(bits, Bits::from_static_bytes(&[2, 3]));
+    let value = Value::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(value, Value::Tagged(3, 0));
+}
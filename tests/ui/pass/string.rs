@@ -0,0 +1,73 @@
+use parsely_rs::*;
+
+#[derive(Debug, ParselyRead, ParselyWrite)]
+struct Foo {
+    #[parsely(context("StringOptions::length_prefixed(u8::BITS)"))]
+    prefixed: String,
+    #[parsely(context("StringOptions::nul_terminated()"))]
+    nul_terminated: String,
+    #[parsely(context("StringOptions::space_padded(6)"))]
+    padded: String,
+    #[parsely(context("StringOptions::to_end().lossy()"))]
+    rest: String,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        3, b'f', b'o', b'o',
+        b'b', b'a', b'r', 0,
+        b'b', b'a', b'z', b' ', b' ', b' ',
+        b'q', b'u', b'x', 0xFF,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(foo.prefixed, "foo");
+    assert_eq!(foo.nul_terminated, "bar");
+    assert_eq!(foo.padded, "baz");
+    assert_eq!(foo.rest, "qux\u{FFFD}");
+
+    let foo = Foo {
+        rest: String::from("qux"),
+        ..foo
+    };
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(&data[..17]));
+
+    // A NUL-padded string ends at its first NUL, ignoring whatever follows it
+    let mut bits = Bits::from_static_bytes(b"ab\0cd\0\0");
+    let value = String::read::<NetworkOrder>(&mut bits, (StringOptions::nul_padded(7),))
+        .expect("successful parse");
+    assert_eq!(value, "ab");
+    assert_eq!(bits.remaining_bytes(), 0);
+    let mut bits = Bits::from_static_bytes(&[b'a', 0, 0xFF]);
+    let value = String::read::<NetworkOrder>(&mut bits, (StringOptions::nul_padded(3),))
+        .expect("successful parse");
+    assert_eq!(value, "a");
+
+    // Invalid UTF-8 is an error unless lossy decoding was requested
+    let mut bits = Bits::from_static_bytes(&[0xFF, 0]);
+    assert!(String::read::<NetworkOrder>(&mut bits, (StringOptions::nul_terminated(),)).is_err());
+
+    // A length prefix longer than the remaining data is an error
+    let mut bits = Bits::from_static_bytes(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, b'a']);
+    assert!(
+        String::read::<NetworkOrder>(&mut bits, (StringOptions::length_prefixed(u64::BITS),))
+            .is_err()
+    );
+
+    // Strings too long for their length prefix or fixed width fail to write
+    let mut bits_mut = BitsMut::new();
+    let long = "a".repeat(300);
+    assert!(
+        long.write::<NetworkOrder>(&mut bits_mut, (StringOptions::length_prefixed(u8::BITS),))
+            .is_err()
+    );
+    assert!(
+        long.write::<NetworkOrder>(&mut bits_mut, (StringOptions::nul_padded(10),))
+            .is_err()
+    );
+}
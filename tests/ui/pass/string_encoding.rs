@@ -0,0 +1,68 @@
+use parsely_rs::*;
+
+#[derive(Debug, ParselyRead, ParselyWrite)]
+struct Foo {
+    #[parsely(context("StringOptions::nul_terminated().encoding(StringEncoding::Ascii)"))]
+    ascii: String,
+    #[parsely(context(
+        "StringOptions::length_prefixed(u8::BITS).encoding(StringEncoding::Latin1)"
+    ))]
+    latin1: String,
+    #[parsely(context("StringOptions::nul_terminated().encoding(StringEncoding::Utf16)"))]
+    utf16: String,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        b'h', b'i', 0,
+        3, b'c', b'a', 0xFF,
+        0, b'o', 0, b'k', 0, 0,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let foo = Foo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(foo.ascii, "hi");
+    assert_eq!(foo.latin1, "ca\u{FF}");
+    assert_eq!(foo.utf16, "ok");
+
+    let mut bits_mut = BitsMut::new();
+    foo.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // UTF-16 code units follow the byte order of the read/write
+    let options = (StringOptions::space_padded(6).encoding(StringEncoding::Utf16),);
+    let mut bits = Bits::from_static_bytes(&[b'o', 0, b'k', 0, b' ', 0]);
+    let value = String::read::<LittleEndian>(&mut bits, options).expect("successful parse");
+    assert_eq!(value, "ok");
+    let mut bits_mut = BitsMut::new();
+    value
+        .write::<LittleEndian>(&mut bits_mut, options)
+        .expect("successful write");
+    assert_eq!(
+        bits_mut.freeze(),
+        Bits::from_static_bytes(&[b'o', 0, b'k', 0, b' ', 0])
+    );
+
+    // Bytes outside of the encoding fail the read unless lossy decoding was requested
+    let options = StringOptions::to_end().encoding(StringEncoding::Ascii);
+    let mut bits = Bits::from_static_bytes(&[b'a', 0x80]);
+    assert!(String::read::<NetworkOrder>(&mut bits, (options,)).is_err());
+    let mut bits = Bits::from_static_bytes(&[b'a', 0x80]);
+    let value = String::read::<NetworkOrder>(&mut bits, (options.lossy(),)).unwrap();
+    assert_eq!(value, "a\u{FFFD}");
+
+    // Characters that can't be encoded fail the write
+    let mut bits_mut = BitsMut::new();
+    assert!(
+        String::from("caf\u{E9}")
+            .write::<NetworkOrder>(&mut bits_mut, (options,))
+            .is_err()
+    );
+    let options = StringOptions::to_end().encoding(StringEncoding::Latin1);
+    assert!(
+        String::from("\u{20AC}")
+            .write::<NetworkOrder>(&mut bits_mut, (options,))
+            .is_err()
+    );
+}
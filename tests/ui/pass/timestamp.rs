@@ -0,0 +1,69 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct SenderInfo {
+    ntp_timestamp: NtpTimestamp,
+    last_sr: CompactNtpTimestamp,
+    created: UnixSeconds,
+    updated: UnixMillis,
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // 2000-01-01T00:00:00.5Z as NTP
+        0xBC, 0x17, 0xC2, 0x00, 0x80, 0x00, 0x00, 0x00,
+        // 1.25 seconds
+        0x00, 0x01, 0x40, 0x00,
+        // 2000-01-01T00:00:00Z
+        0x38, 0x6D, 0x43, 0x80,
+        // 2000-01-01T00:00:00.250Z
+        0x00, 0x00, 0x00, 0xDC, 0x6A, 0xCF, 0xAC, 0xFA,
+    ];
+    let y2k = UNIX_EPOCH + Duration::from_secs(946_684_800);
+
+    let mut bits = Bits::from_static_bytes(data);
+    let info = SenderInfo::read::<NetworkOrder>(&mut bits, ()).expect("successful parse");
+    assert_eq!(
+        info.ntp_timestamp.to_system_time(),
+        y2k + Duration::from_millis(500)
+    );
+    assert_eq!(info.last_sr.to_duration(), Duration::from_millis(1250));
+    assert_eq!(SystemTime::from(info.created), y2k);
+    assert_eq!(
+        SystemTime::from(info.updated),
+        y2k + Duration::from_millis(250)
+    );
+
+    let mut bits_mut = BitsMut::new();
+    info.write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // Conversions from std time types
+    let info = SenderInfo {
+        ntp_timestamp: NtpTimestamp::from(y2k + Duration::from_millis(500)),
+        last_sr: CompactNtpTimestamp::try_from(Duration::from_millis(1250)).unwrap(),
+        created: UnixSeconds::try_from(y2k).unwrap(),
+        updated: UnixMillis::try_from(y2k + Duration::from_millis(250)).unwrap(),
+    };
+    let mut bits = Bits::from_static_bytes(data);
+    assert_eq!(
+        info,
+        SenderInfo::read::<NetworkOrder>(&mut bits, ()).unwrap()
+    );
+    assert_eq!(
+        info.ntp_timestamp.to_compact(),
+        CompactNtpTimestamp(0xC200_8000)
+    );
+
+    // Timestamps after the 2036 NTP rollover
+    let after_rollover = UNIX_EPOCH + Duration::from_secs((1 << 32) - 2_208_988_800 + 100);
+    let ntp = NtpTimestamp::from(after_rollover);
+    assert_eq!(ntp.seconds(), 100);
+    assert_eq!(ntp.to_system_time(), after_rollover);
+
+    assert!(CompactNtpTimestamp::try_from(Duration::from_secs(70_000)).is_err());
+}
@@ -0,0 +1,123 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtpPacket {
+    #[parsely(const_field(u2 = 2), trailing_padding(alignment = 4))]
+    has_padding: bool,
+    extension: bool,
+    csrc_count: u4,
+    marker: bool,
+    payload_type: u7,
+    #[parsely_read(while_pred = "buf.remaining_bytes() > 0")]
+    payload: Vec<u8>,
+}
+
+// An RTCP packet's length field bounds it, so the padding is at the end of the packet rather than
+// at the end of the buffer (which can hold further packets).  The length field comes after the
+// flag, so it's read before the padding is looked for.
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+struct RtcpApp {
+    #[parsely(
+        const_field(u2 = 2),
+        trailing_padding(alignment = 4, length_bytes = "(usize::from(length_field) + 1) * 4")
+    )]
+    has_padding: bool,
+    subtype: u5,
+    packet_type: u8,
+    length_field: u16,
+    #[parsely_read(while_pred = "buf.remaining_bytes() > 0")]
+    data: Vec<u8>,
+}
+
+fn main() {
+    // 3 bytes of padding, the last of which gives the count
+    let data: &[u8] = &[0b10_1_0_0000, 96, 1, 2, 3, 0, 0, 3];
+    let mut bits = Bits::from_static_bytes(data);
+    let packet = RtpPacket::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(
+        packet,
+        RtpPacket {
+            has_padding: true,
+            extension: false,
+            csrc_count: u4::new(0),
+            marker: false,
+            payload_type: u7::new(96),
+            payload: vec![1, 2, 3],
+        }
+    );
+    assert_eq!(bits.remaining_bits(), 0);
+
+    // The payload and padding can be split across chunks of the buffer
+    let mut chained =
+        Bits::from_static_bytes(&data[..3]).chain(Bits::from_static_bytes(&data[3..]));
+    let chained_packet =
+        RtpPacket::read::<NetworkOrder>(&mut chained, ()).expect("successful read");
+    assert_eq!(chained_packet, packet);
+    assert_eq!(chained.remaining_bits(), 0);
+
+    // The padding and flag are generated when writing
+    let mut bits_mut = BitsMut::new();
+    let packet = RtpPacket {
+        has_padding: false,
+        ..packet
+    };
+    packet
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // No padding is needed when the data is already aligned
+    let data: &[u8] = &[0b10_0_0_0000, 96, 1, 2];
+    let mut bits = Bits::from_static_bytes(data);
+    let packet = RtpPacket::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert!(!packet.has_padding);
+    assert_eq!(packet.payload, vec![1, 2]);
+    let mut bits_mut = BitsMut::new();
+    packet
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        // 3 bytes of data and 1 byte of padding
+        0b10_1_00000, 204, 0, 1, 1, 2, 3, 1,
+        // The next packet, without padding
+        0b10_0_00000, 204, 0, 1, 4, 5, 6, 7,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let first = RtcpApp::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert!(first.has_padding);
+    assert_eq!(first.data, vec![1, 2, 3]);
+    let second = RtcpApp::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert!(!second.has_padding);
+    assert_eq!(second.data, vec![4, 5, 6, 7]);
+    assert_eq!(bits.remaining_bits(), 0);
+    // ...which is the same when the packets are split across chunks of the buffer
+    let mut chained =
+        Bits::from_static_bytes(&data[..5]).chain(Bits::from_static_bytes(&data[5..]));
+    assert_eq!(
+        RtcpApp::read::<NetworkOrder>(&mut chained, ()).expect("successful read"),
+        first
+    );
+    assert_eq!(
+        RtcpApp::read::<NetworkOrder>(&mut chained, ()).expect("successful read"),
+        second
+    );
+    let mut bits_mut = BitsMut::new();
+    first
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    second
+        .write::<NetworkOrder>(&mut bits_mut, ())
+        .expect("successful write");
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // A length that's longer than the data
+    let mut bits = Bits::from_static_bytes(&[0b10_1_00000, 204, 0, 2, 1, 2, 3, 1]);
+    assert!(RtcpApp::read::<NetworkOrder>(&mut bits, ()).is_err());
+
+    // A padding count that's bigger than the data
+    let mut bits = Bits::from_static_bytes(&[0b10_1_0_0000, 96, 1, 9]);
+    assert!(RtpPacket::read::<NetworkOrder>(&mut bits, ()).is_err());
+}
@@ -0,0 +1,76 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(validate = "|r| r.report_count as usize == r.reports.len()")]
+struct ReceiverReport {
+    report_count: u8,
+    #[parsely_read(count = "report_count")]
+    reports: Vec<u16>,
+}
+
+fn check_length(frame: &Frame) -> ParselyResult<()> {
+    if (frame.length as usize) < frame.payload.len() {
+        bail!("Length {} doesn't cover the payload", frame.length);
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(validate = "check_length")]
+struct Frame {
+    length: u8,
+    #[parsely_read(count = "2")]
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(
+    key_type = "u8",
+    validate = "|s: &Shape| !matches!(s, Shape::Square(0))"
+)]
+enum Shape {
+    #[parsely(id = 1)]
+    Square(u8),
+    #[parsely(id = 2)]
+    Circle(u8),
+}
+
+fn main() {
+    let mut bits = Bits::from_static_bytes(&[2, 0, 1, 0, 2]);
+    let report = ReceiverReport::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(report.reports, vec![1, 2]);
+
+    // Validation runs before writing
+    let report = ReceiverReport {
+        report_count: 3,
+        ..report
+    };
+    let mut bits_mut = BitsMut::new();
+    assert!(report.write::<NetworkOrder>(&mut bits_mut, ()).is_err());
+    assert_eq!(bits_mut.len_bits(), 0);
+
+    // A validation function's error is attributed to the type
+    let mut bits = Bits::from_static_bytes(&[1, 5, 6]);
+    let Err(error) = Frame::read::<NetworkOrder>(&mut bits, ()) else {
+        panic!("validation should fail");
+    };
+    assert_eq!(error.to_string(), "Validating 'Frame'");
+    assert_eq!(
+        error.root_cause().to_string(),
+        "Length 1 doesn't cover the payload"
+    );
+    let mut bits = Bits::from_static_bytes(&[2, 5, 6]);
+    assert!(Frame::read::<NetworkOrder>(&mut bits, ()).is_ok());
+
+    let mut bits = Bits::from_static_bytes(&[2, 3]);
+    let shape = Shape::read::<NetworkOrder>(&mut bits, ()).expect("successful read");
+    assert_eq!(shape, Shape::Circle(3));
+    let mut bits = Bits::from_static_bytes(&[1, 0]);
+    assert!(Shape::read::<NetworkOrder>(&mut bits, ()).is_err());
+    let mut bits_mut = BitsMut::new();
+    assert!(
+        Shape::Square(0)
+            .write::<NetworkOrder>(&mut bits_mut, ())
+            .is_err()
+    );
+}
@@ -0,0 +1,90 @@
+use parsely_rs::*;
+
+#[derive(Debug, PartialEq, ParselyRead, ParselyWrite)]
+#[parsely(key_type = "u8", required_context("length: usize"))]
+enum Attribute {
+    // The variant's payload is bound to the given length: whatever the fields don't read is
+    // skipped (and padded when writing)
+    #[parsely(id = 1, length_bytes = "length")]
+    Priority { value: u16 },
+    // The variant's fields are padded to a multiple of 4 bytes
+    #[parsely(id = 2, alignment = 4)]
+    Flag(u8),
+    // The matched key is available (as 'key') to the variant's context
+    #[parsely(id = 3, required_context("tag: u8"), context("*key"))]
+    Repeated {
+        #[parsely_read(count = "tag")]
+        values: Vec<u8>,
+    },
+    #[parsely(
+        id = 4,
+        assertion = "|v: &Attribute| matches!(v, Attribute::Percent(p) if *p <= 100)"
+    )]
+    Percent(u8),
+    // A variant with a length has to write a whole number of bytes
+    #[parsely(id = 5, length_bytes = "length")]
+    Short { value: u12 },
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let data: &[u8] = &[
+        1, 0, 42, 0, 0,
+        2, 7, 0, 0, 0,
+        3, 1, 2, 3,
+        4, 50,
+    ];
+    let mut bits = Bits::from_static_bytes(data);
+    let mut bits_mut = BitsMut::new();
+    for expected in [
+        Attribute::Priority { value: 42 },
+        Attribute::Flag(7),
+        Attribute::Repeated {
+            values: vec![1, 2, 3],
+        },
+        Attribute::Percent(50),
+    ] {
+        let attribute = Attribute::read::<NetworkOrder>(&mut bits, (4,)).expect("successful read");
+        assert_eq!(attribute, expected);
+        attribute
+            .write::<NetworkOrder>(&mut bits_mut, (4,))
+            .expect("successful write");
+    }
+    assert_eq!(bits.remaining_bytes(), 0);
+    assert_eq!(bits_mut.freeze(), Bits::from_static_bytes(data));
+
+    // The fields can't read past the variant's length
+    let mut bits = Bits::from_static_bytes(&[1, 0, 42, 0, 0]);
+    assert!(Attribute::read::<NetworkOrder>(&mut bits, (1,)).is_err());
+    // ...and can't write past it
+    let mut bits_mut = BitsMut::new();
+    assert!(
+        Attribute::Priority { value: 42 }
+            .write::<NetworkOrder>(&mut bits_mut, (1,))
+            .is_err()
+    );
+
+    let mut bits = Bits::from_static_bytes(&[4, 101]);
+    assert!(Attribute::read::<NetworkOrder>(&mut bits, (0,)).is_err());
+    let mut bits_mut = BitsMut::new();
+    assert!(
+        Attribute::Percent(101)
+            .write::<NetworkOrder>(&mut bits_mut, (0,))
+            .is_err()
+    );
+
+    let mut bits = Bits::from_static_bytes(&[5, 0x12, 0x30, 0, 0]);
+    let attribute = Attribute::read::<NetworkOrder>(&mut bits, (4,)).expect("successful read");
+    assert_eq!(
+        attribute,
+        Attribute::Short {
+            value: u12::new(0x123)
+        }
+    );
+    let mut bits_mut = BitsMut::new();
+    assert!(
+        attribute
+            .write::<NetworkOrder>(&mut bits_mut, (4,))
+            .is_err()
+    );
+}